@@ -1,19 +1,49 @@
 use std::f64;
 
-use crate::{effector::Spring, types::math::*};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+use crate::{
+    collision::CollisionFilter, effector::Spring, id_map::Id, timestep::Pose, types::math::*,
+};
+
+pub type BodyHandle = Id<Body>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyType {
+    // Moved by forces and collisions
+    #[default]
+    Dynamic,
+    // Never integrated or moved
+    Static,
+    // Moves with the velocity it is given but isn't affected by forces or collisions
+    Kinematic,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Body {
+    pub body_type: BodyType,
     // Not pub? add getters?
     pub linear: LinearState,
     // Only for collidable
     pub restitution: f64,
+    // Friction when not sliding and when sliding, combined with the other body's
+    pub static_friction: f64,
+    pub dynamic_friction: f64,
     // Both optional then remove point shape?
     pub angular: AngularState,
     pub shape: Shape,
+    // Bit flags used by body queries
+    pub tags: u32,
+    pub collision_filter: CollisionFilter,
+    // Reports overlaps without being pushed or pushing other bodies
+    pub sensor: bool,
+    pub(crate) sleeping: bool,
+    // Time spent under the sleep thresholds
+    pub(crate) sleep_timer: f64,
+    // Pose at the last sleep check
+    pub(crate) rest_pose: Pose,
 }
 
-// Maybe add function to apply force at a point
 impl Body {
     // Rename to just new?
     pub fn new_rigid(
@@ -22,26 +52,203 @@ impl Body {
         angular: AngularState,
         shape: Shape,
     ) -> Self {
+        let rest_pose = Pose::new(linear.position, angular.orientation);
+
         Self {
+            body_type: BodyType::Dynamic,
             linear,
             restitution,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
             angular,
             shape,
+            tags: 0,
+            collision_filter: CollisionFilter::default(),
+            sensor: false,
+            sleeping: false,
+            sleep_timer: 0.0,
+            rest_pose,
         }
     }
 
+    // Mass and inertia come from the shape, polygons are recentred on their centroid so the
+    // position ends up at the centre of mass without moving the shape
+    pub fn new_with_density(
+        position: Vector<f64>,
+        orientation: f64,
+        density: f64,
+        restitution: f64,
+        shape: Shape,
+    ) -> Self {
+        let mut body = Body::new_rigid(
+            LinearState::new(position, Vector::zeros(), 0.0),
+            restitution,
+            AngularState::new(orientation, 0.0, 0.0),
+            shape,
+        );
+        body.set_density(density);
+        body
+    }
+
     pub fn new_particle(linear: LinearState, restitution: f64) -> Self {
+        let rest_pose = Pose::new(linear.position, 0.0);
+
         Self {
+            body_type: BodyType::Dynamic,
             linear,
             restitution,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
             // Seems cheaty
             angular: AngularState::new(0.0, 0.0, f64::INFINITY),
             shape: Shape::Point,
+            tags: 0,
+            collision_filter: CollisionFilter::default(),
+            sensor: false,
+            sleeping: false,
+            sleep_timer: 0.0,
+            rest_pose,
+        }
+    }
+
+    pub fn with_body_type(mut self, body_type: BodyType) -> Self {
+        self.body_type = body_type;
+        self
+    }
+
+    pub fn with_friction(mut self, static_friction: f64, dynamic_friction: f64) -> Self {
+        self.static_friction = static_friction;
+        self.dynamic_friction = dynamic_friction;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: u32) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_collision_filter(mut self, collision_filter: CollisionFilter) -> Self {
+        self.collision_filter = collision_filter;
+        self
+    }
+
+    pub fn with_sensor(mut self, sensor: bool) -> Self {
+        self.sensor = sensor;
+        self
+    }
+
+    // Points have no area so get no mass, use particles for them
+    pub fn set_density(&mut self, density: f64) {
+        let offset = self.shape.recentre();
+        self.linear.position += Rotation::new(self.angular.orientation) * offset;
+        self.rest_pose.position = self.linear.position;
+
+        let mass = self.shape.area() * density;
+        self.linear.mass = mass;
+        self.angular.inertia = self.shape.inertia(mass);
+    }
+
+    // Forces and impulses wake the body, points are in world space
+    pub fn apply_force(&mut self, force: Vector<f64>) {
+        self.linear.force += force;
+        self.wake_if_sleeping();
+    }
+
+    pub fn apply_force_at_point(&mut self, force: Vector<f64>, point: Vector<f64>) {
+        self.linear.force += force;
+        self.angular.torque += (point - self.linear.position).perp(&force);
+        self.wake_if_sleeping();
+    }
+
+    pub fn apply_torque(&mut self, torque: f64) {
+        self.angular.torque += torque;
+        self.wake_if_sleeping();
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Vector<f64>) {
+        self.linear.velocity += impulse * self.inverse_mass();
+        self.wake_if_sleeping();
+    }
+
+    pub fn apply_impulse_at_point(&mut self, impulse: Vector<f64>, point: Vector<f64>) {
+        self.linear.velocity += impulse * self.inverse_mass();
+        self.angular.velocity +=
+            (point - self.linear.position).perp(&impulse) * self.inverse_inertia();
+        self.wake_if_sleeping();
+    }
+
+    pub fn apply_torque_impulse(&mut self, impulse: f64) {
+        self.angular.velocity += impulse * self.inverse_inertia();
+        self.wake_if_sleeping();
+    }
+
+    pub fn velocity_at_point(&self, point: Vector<f64>) -> Vector<f64> {
+        let offset = point - self.linear.position;
+        self.linear.velocity + self.angular.velocity * Vector::new(-offset.y, offset.x)
+    }
+
+    pub fn local_to_world(&self, local_point: Vector<f64>) -> Vector<f64> {
+        self.linear.position + Rotation::new(self.angular.orientation) * local_point
+    }
+
+    pub fn world_to_local(&self, point: Vector<f64>) -> Vector<f64> {
+        Rotation::new(-self.angular.orientation) * (point - self.linear.position)
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    // Call after changing a sleeping body by hand so it gets simulated again
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0.0;
+    }
+
+    // Doesn't reset the sleep timer of awake bodies so resting contacts can still sleep
+    fn wake_if_sleeping(&mut self) {
+        if self.sleeping {
+            self.wake_up();
+        }
+    }
+
+    pub(crate) fn put_to_sleep(&mut self) {
+        self.sleeping = true;
+        self.linear.velocity = Vector::zeros();
+        self.angular.velocity = 0.0;
+    }
+
+    // Sleeping and static bodies can't start new collisions between themselves
+    pub fn is_resting(&self) -> bool {
+        self.sleeping || self.body_type == BodyType::Static
+    }
+
+    // Zero unless dynamic so other bodies can't move it
+    pub fn inverse_mass(&self) -> f64 {
+        match self.body_type {
+            BodyType::Dynamic => self.linear.inverse_mass(),
+            BodyType::Static | BodyType::Kinematic => 0.0,
         }
     }
+
+    pub fn inverse_inertia(&self) -> f64 {
+        match self.body_type {
+            BodyType::Dynamic => self.angular.inverse_inertia(),
+            BodyType::Static | BodyType::Kinematic => 0.0,
+        }
+    }
+}
+
+// Infinite or zero values can't be moved so have an inverse of zero
+fn inverse(value: f64) -> f64 {
+    if value > 0.0 && value.is_finite() {
+        1.0 / value
+    } else {
+        0.0
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LinearState {
     pub position: Vector<f64>,
     pub velocity: Vector<f64>,
@@ -58,9 +265,14 @@ impl LinearState {
             mass,
         }
     }
+
+    // Worked out from the mass when needed so changing the mass can't leave it stale
+    pub fn inverse_mass(&self) -> f64 {
+        inverse(self.mass)
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 // Counter clockwise is positive
 pub struct AngularState {
     pub orientation: f64,
@@ -78,9 +290,13 @@ impl AngularState {
             inertia,
         }
     }
+
+    pub fn inverse_inertia(&self) -> f64 {
+        inverse(self.inertia)
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Shape {
     Point,
     Circle(f64),
@@ -127,4 +343,135 @@ impl Shape {
 
         Shape::Polygon { points, axes }
     }
+
+    pub fn area(&self) -> f64 {
+        match self {
+            Shape::Point => 0.0,
+            Shape::Circle(radius) => f64::consts::PI * radius * radius,
+            Shape::Polygon { points, axes: _ } => Shape::signed_area(points).abs(),
+        }
+    }
+
+    // Relative to the body position
+    pub fn centroid(&self) -> Vector<f64> {
+        match self {
+            Shape::Point | Shape::Circle(_) => Vector::zeros(),
+            Shape::Polygon { points, axes: _ } => {
+                let area = Shape::signed_area(points);
+
+                if area == 0.0 {
+                    return Vector::zeros();
+                }
+
+                let mut sum = Vector::zeros();
+
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                    sum += (a + b) * a.perp(&b);
+                }
+
+                sum / (6.0 * area)
+            }
+        }
+    }
+
+    // Moment of inertia around the centroid for a body of the given mass
+    pub fn inertia(&self, mass: f64) -> f64 {
+        match self {
+            Shape::Point => 0.0,
+            Shape::Circle(radius) => mass * radius * radius / 2.0,
+            Shape::Polygon { points, axes: _ } => {
+                let area = Shape::signed_area(points);
+
+                if area == 0.0 {
+                    return 0.0;
+                }
+
+                // Sum over the triangles from the origin to each edge
+                let mut sum = 0.0;
+
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                    sum += a.perp(&b) * (a.dot(&a) + a.dot(&b) + b.dot(&b));
+                }
+
+                let origin_inertia = mass * sum / (12.0 * area);
+
+                // Move the axis from the origin to the centroid
+                origin_inertia - mass * self.centroid().norm_squared()
+            }
+        }
+    }
+
+    // Moves polygon points so the centroid is at the origin, returns how far they moved
+    pub fn recentre(&mut self) -> Vector<f64> {
+        let centroid = self.centroid();
+
+        if let Shape::Polygon { points, axes: _ } = self {
+            for point in points.iter_mut() {
+                *point -= centroid;
+            }
+        }
+
+        centroid
+    }
+
+    // Positive when the points are counter clockwise
+    fn signed_area(points: &[Vector<f64>]) -> f64 {
+        let mut sum = 0.0;
+
+        for i in 0..points.len() {
+            sum += points[i].perp(&points[(i + 1) % points.len()]);
+        }
+
+        sum / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn circle_matches_closed_forms() {
+        let circle = Shape::new_circle(3.0);
+
+        assert_close(circle.area(), f64::consts::PI * 9.0);
+        assert_eq!(circle.centroid(), Vector::zeros());
+        assert_close(circle.inertia(2.0), 2.0 * 9.0 / 2.0);
+    }
+
+    #[test]
+    fn rectangle_matches_closed_forms() {
+        let rectangle = Shape::new_rectangle(Vector::new(4.0, 2.0));
+
+        assert_close(rectangle.area(), 8.0);
+        assert_close(rectangle.centroid().norm(), 0.0);
+        assert_close(rectangle.inertia(3.0), 3.0 * (16.0 + 4.0) / 12.0);
+    }
+
+    #[test]
+    fn offset_triangle_matches_closed_forms() {
+        // Right triangle with both legs 3, clockwise to check the winding doesn't matter
+        let mut triangle = Shape::new_polygon(vec![
+            Vector::new(1.0, 1.0),
+            Vector::new(1.0, 4.0),
+            Vector::new(4.0, 1.0),
+        ]);
+
+        assert_close(triangle.area(), 4.5);
+        assert_close((triangle.centroid() - Vector::new(2.0, 2.0)).norm(), 0.0);
+        // Around the centroid a right triangle is m (a^2 + b^2) / 18
+        assert_close(triangle.inertia(2.0), 2.0 * (9.0 + 9.0) / 18.0);
+
+        let moved = triangle.recentre();
+
+        assert_close((moved - Vector::new(2.0, 2.0)).norm(), 0.0);
+        assert_close(triangle.centroid().norm(), 0.0);
+        assert_close(triangle.inertia(2.0), 2.0);
+    }
 }
@@ -1,42 +1,226 @@
 pub mod default;
+pub mod sequential_impulse;
 
-use crate::body::{AngularState, Body, LinearState, Shape};
+use crate::body::{AngularState, Body, BodyHandle, LinearState, Shape};
 use crate::id_map::{Id, IdMap};
-use crate::types::math::*;
+use crate::membership::{BodyGroup, BodyMembership};
+use crate::types::{AsAny, math::*};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::collections::HashMap;
 
-pub trait CollisionPipeline {
+pub trait CollisionPipeline: Any + AsAny + BodyMembership {
     fn init(&mut self, bodies: &mut IdMap<Body>);
     fn handle(&mut self, bodies: &mut IdMap<Body>);
+
+    // Pairs of bodies currently touching, used to build sleep islands
+    fn links(&self) -> Vec<[BodyHandle; 2]> {
+        Vec::new()
+    }
+
+    // Takes the events published since the last drain, they're kept until then however many
+    // steps run
+    fn drain_events(&mut self) -> Vec<CollisionEvent> {
+        Vec::new()
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        None
+    }
+}
+
+pub type BoxedCollisionPipeline = Box<dyn CollisionPipeline + Send + Sync>;
+pub type PipelineHandle = Id<BoxedCollisionPipeline>;
+
+// Decides which pairs of bodies in a pipeline can collide
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionFilter {
+    // Layers the body is in
+    pub layers: u32,
+    // Layers the body collides with
+    pub mask: u32,
+    // Bodies sharing a group never collide, like the parts of a ragdoll
+    pub group: Option<u32>,
+}
+
+impl CollisionFilter {
+    pub fn new(layers: u32, mask: u32) -> Self {
+        Self {
+            layers,
+            mask,
+            group: None,
+        }
+    }
+
+    pub fn with_group(mut self, group: u32) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    // Both bodies have to be in a layer the other collides with
+    pub fn can_collide(&self, other: &CollisionFilter) -> bool {
+        if self.group.is_some() && self.group == other.group {
+            return false;
+        }
+
+        self.layers & other.mask != 0 && other.layers & self.mask != 0
+    }
+}
+
+// In the first layer and collides with everything
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::new(1, u32::MAX)
+    }
 }
 
 pub trait CollisionDetection {
-    fn init(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>);
-    fn detect(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) -> Vec<CollisionData>;
+    fn init(&mut self, managed_bodies: &[BodyHandle], bodies: &mut IdMap<Body>);
+    fn detect(
+        &mut self,
+        managed_bodies: &[BodyHandle],
+        bodies: &mut IdMap<Body>,
+    ) -> Vec<CollisionData>;
 }
 
 pub trait BroadPhase {
     // Remove init into another trait
-    fn init(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>);
+    fn init(&mut self, managed_bodies: &[BodyHandle], bodies: &mut IdMap<Body>);
     // Better name
-    fn cull(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) -> Vec<[Id; 2]>;
+    fn cull(
+        &mut self,
+        managed_bodies: &[BodyHandle],
+        bodies: &mut IdMap<Body>,
+    ) -> Vec<[BodyHandle; 2]>;
 }
 
 pub trait NarrowPhase {
-    fn init(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>);
-    fn detect(&mut self, body_pairs: Vec<[Id; 2]>, bodies: &mut IdMap<Body>) -> Vec<CollisionData>;
+    fn init(&mut self, managed_bodies: &[BodyHandle], bodies: &mut IdMap<Body>);
+    fn detect(
+        &mut self,
+        body_pairs: Vec<[BodyHandle; 2]>,
+        bodies: &mut IdMap<Body>,
+    ) -> Vec<CollisionData>;
 }
 
 pub trait CollisionResolution {
     fn init(&mut self, bodies: &mut IdMap<Body>);
-    fn resolve(&mut self, collisions: Vec<CollisionData>, bodies: &mut IdMap<Body>);
+    // Fills in the impulses applied at each contact point
+    fn resolve(&mut self, collisions: &mut [CollisionData], bodies: &mut IdMap<Body>);
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CollisionData {
-    pub bodies: [Id; 2],
-    pub points: Vec<Vector<f64>>,
+    pub bodies: [BodyHandle; 2],
+    pub manifold: ContactManifold,
     // From a to b
     pub normal: Vector<f64>,
+    // Deepest overlap along the normal
     pub depth: f64,
+    // One of the bodies is a sensor so the overlap gets no response
+    pub sensor: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContactPoint {
+    pub position: Vector<f64>,
+    pub depth: f64,
+    // Identifies the point across steps
+    pub feature: u32,
+    // Impulses applied by the resolver, accumulated over its iterations
+    pub normal_impulse: f64,
+    pub tangent_impulse: f64,
+}
+
+impl ContactPoint {
+    pub fn new(position: Vector<f64>, depth: f64, feature: u32) -> Self {
+        Self {
+            position,
+            depth,
+            feature,
+            normal_impulse: 0.0,
+            tangent_impulse: 0.0,
+        }
+    }
+}
+
+// Two points are enough to hold up a flat edge in 2D
+pub const MAX_MANIFOLD_POINTS: usize = 2;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ContactManifold {
+    points: Vec<ContactPoint>,
+}
+
+impl ContactManifold {
+    pub fn new() -> Self {
+        Self { points: Vec::new() }
+    }
+
+    // Once full the shallowest point is replaced if the new one is deeper
+    pub fn add_point(&mut self, point: ContactPoint) {
+        if self.points.len() < MAX_MANIFOLD_POINTS {
+            self.points.push(point);
+            return;
+        }
+
+        let shallowest = self
+            .points
+            .iter_mut()
+            .min_by(|a, b| a.depth.total_cmp(&b.depth))
+            .unwrap();
+
+        if point.depth > shallowest.depth {
+            *shallowest = point;
+        }
+    }
+
+    pub fn points(&self) -> &[ContactPoint] {
+        &self.points
+    }
+
+    pub fn points_mut(&mut self) -> &mut [ContactPoint] {
+        &mut self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // Carries the impulses of points with the same feature over from the last step
+    pub fn match_points(&mut self, previous: &ContactManifold) {
+        for point in &mut self.points {
+            if let Some(old) = previous.points.iter().find(|p| p.feature == point.feature) {
+                point.normal_impulse = old.normal_impulse;
+                point.tangent_impulse = old.tangent_impulse;
+            }
+        }
+    }
+
+    pub fn normal_impulse(&self) -> f64 {
+        self.points.iter().map(|p| p.normal_impulse).sum()
+    }
+
+    pub fn tangent_impulse(&self) -> f64 {
+        self.points.iter().map(|p| p.tangent_impulse).sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionEventKind {
+    Began,
+    Persisting,
+    Ended,
+}
+
+// For sensors began and ended are when a body enters and exits the sensor
+#[derive(Clone, Debug)]
+pub struct CollisionEvent {
+    pub kind: CollisionEventKind,
+    // Ended events have the data from the last step the bodies were touching
+    pub collision: CollisionData,
 }
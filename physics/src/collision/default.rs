@@ -1,34 +1,171 @@
-use std::f64;
+use std::{collections::BTreeMap, f64};
 
-use crate::{collision::*, id_map::Id};
+use serde::{Deserialize, Serialize};
 
-pub struct DefaultCollisionPipeline {
-    bodies: Vec<Id>,
+use crate::{collision::*, membership::BodySet};
+
+// Marks contact points made by clipping against either end of the reference edge
+const CLIPPED_START: u32 = 1 << 8;
+const CLIPPED_END: u32 = 1 << 9;
+
+const REFERENCE_TOLERANCE: f64 = 0.01;
+const BLOCK_TOLERANCE: f64 = 1e-3;
+
+// The resolver can be swapped, like for the sequential impulse resolver
+#[derive(Serialize, Deserialize)]
+pub struct DefaultCollisionPipeline<R = DefaultCollisionResolver> {
+    bodies: BodySet,
     detector: DefaultCollisionDetector,
-    resolver: DefaultCollisionResolver,
+    resolver: R,
+    // Pairs touching after the last step, keyed with the lower id first
+    contacts: BTreeMap<[BodyHandle; 2], CollisionData>,
+    #[serde(skip)]
+    events: Vec<CollisionEvent>,
 }
 
 impl DefaultCollisionPipeline {
-    pub fn new(bodies: Vec<Id>) -> Self {
+    pub fn new(bodies: impl Into<BodySet>) -> Self {
+        DefaultCollisionPipeline::with_resolver(bodies, DefaultCollisionResolver::new())
+    }
+}
+
+impl<R: CollisionResolution> DefaultCollisionPipeline<R> {
+    pub fn with_resolver(bodies: impl Into<BodySet>, resolver: R) -> Self {
         Self {
-            bodies,
+            bodies: bodies.into(),
             detector: DefaultCollisionDetector::new(),
-            resolver: DefaultCollisionResolver::new(),
+            resolver,
+            contacts: BTreeMap::new(),
+            events: Vec::new(),
         }
     }
+
+    fn pair_key(bodies: [BodyHandle; 2]) -> [BodyHandle; 2] {
+        let [a, b] = bodies;
+        if a <= b { [a, b] } else { [b, a] }
+    }
+
+    fn publish_events(&mut self, collisions: Vec<CollisionData>, bodies: &IdMap<Body>) {
+        let mut contacts = BTreeMap::new();
+
+        for collision in collisions {
+            let key = Self::pair_key(collision.bodies);
+
+            let kind = if self.contacts.contains_key(&key) {
+                CollisionEventKind::Persisting
+            } else {
+                CollisionEventKind::Began
+            };
+
+            self.events.push(CollisionEvent {
+                kind,
+                collision: collision.clone(),
+            });
+            contacts.insert(key, collision);
+        }
+
+        for (key, collision) in &self.contacts {
+            if contacts.contains_key(key) {
+                continue;
+            }
+
+            // Resting pairs aren't checked so keep their contact for the sleep islands
+            let resting = key
+                .iter()
+                .all(|id| bodies.get(*id).is_some_and(|body| body.is_resting()));
+
+            if resting {
+                contacts.insert(*key, collision.clone());
+            } else {
+                self.events.push(CollisionEvent {
+                    kind: CollisionEventKind::Ended,
+                    collision: collision.clone(),
+                });
+            }
+        }
+
+        self.contacts = contacts;
+    }
 }
 
-impl CollisionPipeline for DefaultCollisionPipeline {
+impl<R: CollisionResolution> BodyGroup for DefaultCollisionPipeline<R> {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
+    }
+
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+        self.detector.body_removed(body);
+
+        // End any contacts the body was part of
+        let ended: Vec<[BodyHandle; 2]> = self
+            .contacts
+            .keys()
+            .filter(|key| key.contains(&body))
+            .copied()
+            .collect();
+
+        for key in ended {
+            let collision = self.contacts.remove(&key).unwrap();
+            self.events.push(CollisionEvent {
+                kind: CollisionEventKind::Ended,
+                collision,
+            });
+        }
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl<R: CollisionResolution> BodyMembership for DefaultCollisionPipeline<R> {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
+    }
+}
+
+impl<R: CollisionResolution + 'static> CollisionPipeline for DefaultCollisionPipeline<R> {
     fn init(&mut self, bodies: &mut IdMap<Body>) {
-        self.detector.init(&self.bodies, bodies);
+        let managed_bodies = self.bodies.resolve(bodies);
+        self.detector.init(&managed_bodies, bodies);
     }
 
     fn handle(&mut self, bodies: &mut IdMap<Body>) {
-        let collisions = self.detector.detect(&self.bodies, bodies);
-        self.resolver.resolve(collisions, bodies);
+        let managed_bodies = self.bodies.resolve(bodies);
+        let mut collisions = self.detector.detect(&managed_bodies, bodies);
+
+        // Manifolds persist between steps so resolvers can start from the last impulses
+        for collision in &mut collisions {
+            let previous = self
+                .contacts
+                .get(&Self::pair_key(collision.bodies))
+                .filter(|previous| previous.bodies == collision.bodies);
+
+            if let Some(previous) = previous {
+                collision.manifold.match_points(&previous.manifold);
+            }
+        }
+
+        self.resolver.resolve(&mut collisions, bodies);
+        self.publish_events(collisions, bodies);
+    }
+
+    fn links(&self) -> Vec<[BodyHandle; 2]> {
+        self.contacts.keys().copied().collect()
+    }
+
+    fn drain_events(&mut self) -> Vec<CollisionEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DefaultCollisionDetector {
     broad_phase: DefaultBroadPhase,
     narrow_phase: DefaultNarrowPhase,
@@ -43,19 +180,30 @@ impl DefaultCollisionDetector {
     }
 }
 
+impl BodyMembership for DefaultCollisionDetector {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.broad_phase.body_removed(body)
+    }
+}
+
 impl CollisionDetection for DefaultCollisionDetector {
-    fn init(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) {
+    fn init(&mut self, managed_bodies: &[BodyHandle], bodies: &mut IdMap<Body>) {
         self.broad_phase.init(managed_bodies, bodies);
     }
 
-    fn detect(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) -> Vec<CollisionData> {
+    fn detect(
+        &mut self,
+        managed_bodies: &[BodyHandle],
+        bodies: &mut IdMap<Body>,
+    ) -> Vec<CollisionData> {
         let body_pairs = self.broad_phase.cull(managed_bodies, bodies);
         self.narrow_phase.detect(body_pairs, bodies)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DefaultBroadPhase {
-    circles: HashMap<Id, f64>,
+    circles: HashMap<BodyHandle, f64>,
 }
 
 impl DefaultBroadPhase {
@@ -66,32 +214,28 @@ impl DefaultBroadPhase {
     }
 }
 
+impl BodyMembership for DefaultBroadPhase {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.circles.remove(&body);
+        true
+    }
+}
+
 impl BroadPhase for DefaultBroadPhase {
-    fn init(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) {
+    fn init(&mut self, managed_bodies: &[BodyHandle], bodies: &mut IdMap<Body>) {
         for id in managed_bodies {
             if let Some(body) = bodies.get(*id) {
-                match &body.shape {
-                    Shape::Point => {
-                        self.circles.insert(*id, 0.0);
-                    }
-                    Shape::Circle(radius) => {
-                        self.circles.insert(*id, *radius);
-                    }
-                    Shape::Polygon { points, axes: _ } => {
-                        let mut max_radius: f64 = 0.0;
-
-                        for point in points {
-                            max_radius = max_radius.max(point.magnitude());
-                        }
-
-                        self.circles.insert(*id, max_radius);
-                    }
-                }
+                self.circles
+                    .insert(*id, DefaultBroadPhase::bounding_radius(&body.shape));
             }
         }
     }
 
-    fn cull(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) -> Vec<[Id; 2]> {
+    fn cull(
+        &mut self,
+        managed_bodies: &[BodyHandle],
+        bodies: &mut IdMap<Body>,
+    ) -> Vec<[BodyHandle; 2]> {
         let mut pairs = Vec::new();
 
         for i in 0..managed_bodies.len() {
@@ -100,9 +244,27 @@ impl BroadPhase for DefaultBroadPhase {
                 let Some(a) = bodies.get(a_id) else { continue };
                 let Some(b) = bodies.get(b_id) else { continue };
 
+                if !a.collision_filter.can_collide(&b.collision_filter) {
+                    continue;
+                }
+
+                if a.is_resting() && b.is_resting() {
+                    continue;
+                }
+
                 let distance = b.linear.position.metric_distance(&a.linear.position);
 
-                if distance < self.circles.get(&a_id).unwrap() + self.circles.get(&b_id).unwrap() {
+                // Bodies added after init won't have a circle yet
+                let a_radius = *self
+                    .circles
+                    .entry(a_id)
+                    .or_insert_with(|| DefaultBroadPhase::bounding_radius(&a.shape));
+                let b_radius = *self
+                    .circles
+                    .entry(b_id)
+                    .or_insert_with(|| DefaultBroadPhase::bounding_radius(&b.shape));
+
+                if distance < a_radius + b_radius {
                     pairs.push([a_id, b_id]);
                 }
             }
@@ -112,6 +274,25 @@ impl BroadPhase for DefaultBroadPhase {
     }
 }
 
+impl DefaultBroadPhase {
+    fn bounding_radius(shape: &Shape) -> f64 {
+        match shape {
+            Shape::Point => 0.0,
+            Shape::Circle(radius) => *radius,
+            Shape::Polygon { points, axes: _ } => {
+                let mut max_radius: f64 = 0.0;
+
+                for point in points {
+                    max_radius = max_radius.max(point.magnitude());
+                }
+
+                max_radius
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct DefaultNarrowPhase {
     collisions: u64,
 }
@@ -123,10 +304,14 @@ impl DefaultNarrowPhase {
 }
 
 impl NarrowPhase for DefaultNarrowPhase {
-    fn init(&mut self, managed_bodies: &Vec<Id>, bodies: &mut IdMap<Body>) {}
+    fn init(&mut self, managed_bodies: &[BodyHandle], bodies: &mut IdMap<Body>) {}
 
     // Doesnt work for points
-    fn detect(&mut self, body_pairs: Vec<[Id; 2]>, bodies: &mut IdMap<Body>) -> Vec<CollisionData> {
+    fn detect(
+        &mut self,
+        body_pairs: Vec<[BodyHandle; 2]>,
+        bodies: &mut IdMap<Body>,
+    ) -> Vec<CollisionData> {
         let mut collisions = Vec::new();
 
         for pair in body_pairs {
@@ -284,7 +469,8 @@ impl NarrowPhase for DefaultNarrowPhase {
                 ),
             };
 
-            if let Some(collision) = collision {
+            if let Some(mut collision) = collision {
+                collision.sensor = a.sensor || b.sensor;
                 self.collisions += 1;
                 collisions.push(collision);
             }
@@ -296,10 +482,10 @@ impl NarrowPhase for DefaultNarrowPhase {
 
 impl DefaultNarrowPhase {
     fn detect_circle_circle(
-        a_id: Id,
+        a_id: BodyHandle,
         a_position: &Vector<f64>,
         a_radius: f64,
-        b_id: Id,
+        b_id: BodyHandle,
         b_position: &Vector<f64>,
         b_radius: f64,
     ) -> Option<CollisionData> {
@@ -312,11 +498,15 @@ impl DefaultNarrowPhase {
         let point = (a_point + b_point) / 2.0;
 
         if depth > 0.0 {
+            let mut manifold = ContactManifold::new();
+            manifold.add_point(ContactPoint::new(point, depth, 0));
+
             Some(CollisionData {
                 bodies: [a_id, b_id],
-                points: vec![point],
+                manifold,
                 normal,
                 depth,
+                sensor: false,
             })
         } else {
             None
@@ -324,11 +514,11 @@ impl DefaultNarrowPhase {
     }
 
     fn detect_sat_circle(
-        a_id: Id,
+        a_id: BodyHandle,
         a_position: &Vector<f64>,
         a_points: &Vec<Vector<f64>>,
         a_axes: &Vec<Vector<f64>>,
-        b_id: Id,
+        b_id: BodyHandle,
         b_position: &Vector<f64>,
         b_radius: f64,
     ) -> Option<CollisionData> {
@@ -352,8 +542,9 @@ impl DefaultNarrowPhase {
 
         let mut min_penetration = f64::INFINITY;
         let mut min_axis: Option<&Vector<f64>> = None;
+        let mut min_axis_index = 0;
 
-        for axis in &axes {
+        for (index, axis) in axes.iter().enumerate() {
             let (a_min, a_max) = DefaultNarrowPhase::project(a_points, axis);
             let b_position = b_position.dot(&axis);
             let (b_min, b_max) = (b_position - b_radius, b_position + b_radius);
@@ -368,6 +559,7 @@ impl DefaultNarrowPhase {
                 if penetration < min_penetration {
                     min_penetration = penetration;
                     min_axis = Some(axis);
+                    min_axis_index = index;
                 }
             }
         }
@@ -383,21 +575,30 @@ impl DefaultNarrowPhase {
 
         let point = b_position - (b_radius - min_penetration / 2.0) * collision_normal;
 
+        // The feature is the axis the point was pushed out along
+        let mut manifold = ContactManifold::new();
+        manifold.add_point(ContactPoint::new(
+            point,
+            min_penetration,
+            min_axis_index as u32,
+        ));
+
         Some(CollisionData {
             bodies: [a_id, b_id],
-            points: vec![point],
+            manifold,
             normal: collision_normal,
             depth: min_penetration,
+            sensor: false,
         })
     }
 
     // Separating Axis Theorem (SAT)
     fn detect_sat(
-        a_id: Id,
+        a_id: BodyHandle,
         a_position: &Vector<f64>,
         a_points: &Vec<Vector<f64>>,
         a_axes: &Vec<Vector<f64>>,
-        b_id: Id,
+        b_id: BodyHandle,
         b_position: &Vector<f64>,
         b_points: &Vec<Vector<f64>>,
         b_axes: &Vec<Vector<f64>>,
@@ -435,10 +636,13 @@ impl DefaultNarrowPhase {
             -min_axis
         };
 
-        let a_edge = DefaultNarrowPhase::farthest_perpendicular_edge(a_points, &collision_normal);
-        let b_edge = DefaultNarrowPhase::farthest_perpendicular_edge(b_points, &-collision_normal);
+        let (a_edge, a_indices) =
+            DefaultNarrowPhase::farthest_perpendicular_edge(a_points, &collision_normal);
+        let (b_edge, b_indices) =
+            DefaultNarrowPhase::farthest_perpendicular_edge(b_points, &-collision_normal);
 
-        let (reference, incident) = if (a_edge.0 - a_edge.1)
+        // Leans towards a so near ties like stacked boxes don't swap the reference every step
+        let reference_is_a = (a_edge.0 - a_edge.1)
             .normalize()
             .dot(&collision_normal)
             .abs()
@@ -446,43 +650,82 @@ impl DefaultNarrowPhase {
                 .normalize()
                 .dot(&collision_normal)
                 .abs()
-        {
-            (a_edge, b_edge)
-        } else {
-            (b_edge, a_edge)
-        };
+                + REFERENCE_TOLERANCE;
+
+        let (reference, reference_indices, reference_len, incident, incident_indices) =
+            if reference_is_a {
+                (a_edge, a_indices, a_points.len(), b_edge, b_indices)
+            } else {
+                (b_edge, b_indices, b_points.len(), a_edge, a_indices)
+            };
+
+        // Put the edge in winding order and name it by its first point, either end can be the
+        // farthest point so this keeps the features the same between steps
+        let (reference, reference_index) =
+            if reference_indices.1 == (reference_indices.0 + 1) % reference_len {
+                (reference, reference_indices.0)
+            } else {
+                ((reference.1, reference.0), reference_indices.1)
+            };
 
         let reference_edge = (reference.1 - reference.0).normalize();
 
-        let mut clipped =
-            DefaultNarrowPhase::clip(incident, reference_edge, reference_edge.dot(&reference.0));
+        // Each point keeps the index of the incident vertex it came from
+        let incident = [
+            (incident.0, incident_indices.0 as u32),
+            (incident.1, incident_indices.1 as u32),
+        ];
+
+        let mut clipped = DefaultNarrowPhase::clip(
+            incident,
+            reference_edge,
+            reference_edge.dot(&reference.0),
+            CLIPPED_START,
+        );
 
         if clipped.len() < 2 {
             return None;
         }
 
         clipped = DefaultNarrowPhase::clip(
-            (clipped[0], clipped[1]),
+            [clipped[0], clipped[1]],
             -reference_edge,
             -reference_edge.dot(&reference.1),
+            CLIPPED_END,
         );
 
         if clipped.len() < 2 {
             return None;
         }
 
-        let reference_normal = Vector::new(-reference_edge.y, reference_edge.x);
+        // Keep the points behind the reference face, its winding can go either way so use the
+        // collision normal pointing out of the reference body
+        let reference_normal = if reference_is_a {
+            collision_normal
+        } else {
+            -collision_normal
+        };
         let offset = reference_normal.dot(&reference.0);
-        let points: Vec<Vector<f64>> = clipped
-            .into_iter()
-            .filter(|p| p.dot(&reference_normal) >= offset)
-            .collect();
+        let mut manifold = ContactManifold::new();
+
+        for (point, incident_feature) in clipped {
+            let depth = offset - point.dot(&reference_normal);
+
+            if depth >= 0.0 {
+                // Which body and edge is the reference plus where the point came from
+                let feature = (reference_is_a as u32) << 24
+                    | (reference_index as u32 & 0xff) << 16
+                    | incident_feature;
+                manifold.add_point(ContactPoint::new(point, depth, feature));
+            }
+        }
 
         Some(CollisionData {
             bodies: [a_id, b_id],
-            points,
+            manifold,
             normal: collision_normal,
             depth: min_penetration,
+            sensor: false,
         })
     }
 
@@ -499,10 +742,11 @@ impl DefaultNarrowPhase {
         (min, max)
     }
 
+    // Also returns the indices of the edge's points
     fn farthest_perpendicular_edge(
         points: &Vec<Vector<f64>>,
         axis: &Vector<f64>,
-    ) -> (Vector<f64>, Vector<f64>) {
+    ) -> ((Vector<f64>, Vector<f64>), (usize, usize)) {
         let mut max = f64::NEG_INFINITY;
         let mut max_point_index = 0;
 
@@ -515,14 +759,15 @@ impl DefaultNarrowPhase {
             }
         }
 
-        let point = points[max_point_index];
-        let (a, b) = (
-            points[(max_point_index + points.len() - 1) % points.len()],
-            points[(max_point_index + 1) % points.len()],
+        let (a_index, b_index) = (
+            (max_point_index + points.len() - 1) % points.len(),
+            (max_point_index + 1) % points.len(),
         );
+        let point = points[max_point_index];
+        let (a, b) = (points[a_index], points[b_index]);
 
-        let a_edge = (point, a);
-        let b_edge = (point, b);
+        let a_edge = ((point, a), (max_point_index, a_index));
+        let b_edge = ((point, b), (max_point_index, b_index));
 
         if (point - a).normalize().dot(&axis).abs() <= (point - b).normalize().dot(&axis).abs() {
             a_edge
@@ -531,32 +776,40 @@ impl DefaultNarrowPhase {
         }
     }
 
+    // Points carry a feature id, a new point gets the id of the point clipped off plus the flag
     fn clip(
-        edge: (Vector<f64>, Vector<f64>),
+        edge: [(Vector<f64>, u32); 2],
         normal: Vector<f64>,
         offset: f64,
-    ) -> Vec<Vector<f64>> {
+        clipped_flag: u32,
+    ) -> Vec<(Vector<f64>, u32)> {
         let mut points = Vec::new();
 
-        let a = edge.0.dot(&normal) - offset;
-        let b = edge.1.dot(&normal) - offset;
+        let [(a_point, a_feature), (b_point, b_feature)] = edge;
+        let a = a_point.dot(&normal) - offset;
+        let b = b_point.dot(&normal) - offset;
 
         if a >= 0.0 {
-            points.push(edge.0);
+            points.push(edge[0]);
         }
 
         if b >= 0.0 {
-            points.push(edge.1);
+            points.push(edge[1]);
         }
 
         if a * b < 0.0 {
-            points.push((edge.1 - edge.0) * (a / (a - b)) + edge.0);
+            let clipped_feature = if a < 0.0 { a_feature } else { b_feature };
+            points.push((
+                (b_point - a_point) * (a / (a - b)) + a_point,
+                clipped_feature | clipped_flag,
+            ));
         }
 
         points
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DefaultCollisionResolver {
     correction_level: f64,
     correction_tolerance: f64,
@@ -571,12 +824,108 @@ impl DefaultCollisionResolver {
     }
 }
 
+impl DefaultCollisionResolver {
+    fn denominator(a: &Body, b: &Body, point: &Vector<f64>, direction: &Vector<f64>) -> f64 {
+        let a_to_point = point - a.linear.position;
+        let b_to_point = point - b.linear.position;
+
+        a.inverse_mass()
+            + b.inverse_mass()
+            + a_to_point.perp(direction).powi(2) * a.inverse_inertia()
+            + b_to_point.perp(direction).powi(2) * b.inverse_inertia()
+    }
+
+    // How much an impulse at one point changes the normal velocity at the other
+    fn coupling(a: &Body, b: &Body, i: &Vector<f64>, j: &Vector<f64>, normal: &Vector<f64>) -> f64 {
+        a.inverse_mass()
+            + b.inverse_mass()
+            + (i - a.linear.position).perp(normal)
+                * (j - a.linear.position).perp(normal)
+                * a.inverse_inertia()
+            + (i - b.linear.position).perp(normal)
+                * (j - b.linear.position).perp(normal)
+                * b.inverse_inertia()
+    }
+
+    // Solves both points together so a box resting on two corners gets pushed on both evenly.
+    // Contacts can only push, so a point that would need pulling gets no impulse
+    fn normal_impulses(
+        a: &Body,
+        b: &Body,
+        manifold: &ContactManifold,
+        normal: &Vector<f64>,
+        restitution: f64,
+    ) -> Vec<f64> {
+        let points: Vec<Vector<f64>> = manifold.points().iter().map(|p| p.position).collect();
+
+        // The change in normal velocity wanted at each point
+        let targets: Vec<f64> = points
+            .iter()
+            .map(|p| {
+                -(1.0 + restitution)
+                    * (b.velocity_at_point(*p) - a.velocity_at_point(*p)).dot(normal)
+            })
+            .collect();
+
+        let k = |i: usize, j: usize| {
+            DefaultCollisionResolver::coupling(a, b, &points[i], &points[j], normal)
+        };
+
+        if points.len() == 2 {
+            let (k00, k01, k11) = (k(0, 0), k(0, 1), k(1, 1));
+            let determinant = k00 * k11 - k01 * k01;
+
+            if determinant > BLOCK_TOLERANCE * k00 * k11 {
+                let (t0, t1) = (targets[0], targets[1]);
+
+                // Tries both points pushing, then only one of them, then neither. The first
+                // where every impulse pushes and no point is left moving in too fast is the
+                // answer
+                let both = [
+                    (k11 * t0 - k01 * t1) / determinant,
+                    (k00 * t1 - k01 * t0) / determinant,
+                ];
+                if both[0] >= 0.0 && both[1] >= 0.0 {
+                    return both.to_vec();
+                }
+
+                let first = t0 / k00;
+                if first >= 0.0 && k01 * first >= t1 {
+                    return vec![first, 0.0];
+                }
+
+                let second = t1 / k11;
+                if second >= 0.0 && k01 * second >= t0 {
+                    return vec![0.0, second];
+                }
+
+                return vec![0.0, 0.0];
+            }
+        }
+
+        // Points on top of each other can't be solved together so they share the push
+        let share = 1.0 / points.len() as f64;
+
+        (0..points.len())
+            .map(|i| {
+                let kii = k(i, i);
+                if kii > 0.0 {
+                    (targets[i] / kii * share).max(0.0)
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
 impl CollisionResolution for DefaultCollisionResolver {
     fn init(&mut self, bodies: &mut IdMap<Body>) {}
 
-    fn resolve(&mut self, collisions: Vec<CollisionData>, bodies: &mut IdMap<Body>) {
+    fn resolve(&mut self, collisions: &mut [CollisionData], bodies: &mut IdMap<Body>) {
         for collision in collisions {
-            if collision.points.len() == 0 {
+            // Sensors only report overlaps
+            if collision.manifold.is_empty() || collision.sensor {
                 continue;
             }
 
@@ -586,48 +935,77 @@ impl CollisionResolution for DefaultCollisionResolver {
             };
 
             let restitution = a.restitution * b.restitution;
+            let normal = collision.normal;
+
+            // Solved from nothing every step, the impulses matched from the last step are
+            // left for resolvers that warm start and are overwritten here
+            let impulses = DefaultCollisionResolver::normal_impulses(
+                a,
+                b,
+                &collision.manifold,
+                &normal,
+                restitution,
+            );
+
+            for (point, impulse) in collision.manifold.points_mut().iter_mut().zip(impulses) {
+                point.normal_impulse = impulse;
+            }
 
-            // Must be a more correct way for multiple points
-            let point = collision
-                .points
-                .iter()
-                .fold(Vector::new(0.0, 0.0), |a, p| a + p)
-                / collision.points.len() as f64;
+            for point in collision.manifold.points() {
+                let impulse = point.normal_impulse * normal;
+                a.apply_impulse_at_point(-impulse, point.position);
+                b.apply_impulse_at_point(impulse, point.position);
+            }
 
-            let a_to_point = point - a.linear.position;
-            let a_to_point_perp = Vector::new(-a_to_point.y, a_to_point.x);
-            let b_to_point = point - b.linear.position;
-            let b_to_point_perp = Vector::new(-b_to_point.y, b_to_point.x);
+            // Friction against the sliding left after the normal impulse, a point at a time
+            let static_friction = (a.static_friction * b.static_friction).sqrt();
+            let dynamic_friction = (a.dynamic_friction * b.dynamic_friction).sqrt();
 
-            let relative_velocity = (b.linear.velocity + b.angular.velocity * b_to_point_perp)
-                - (a.linear.velocity + a.angular.velocity * a_to_point_perp);
+            for point in collision.manifold.points_mut() {
+                point.tangent_impulse = 0.0;
 
-            // Separate calculation for none angular bodies
-            // What happens if one can rotate but other cant?
-            // Can be 0?
-            let denominator = 1.0 / a.linear.mass
-                + 1.0 / b.linear.mass
-                + a_to_point.perp(&collision.normal).powi(2) / a.angular.inertia
-                + b_to_point.perp(&collision.normal).powi(2) / b.angular.inertia;
+                let relative_velocity =
+                    b.velocity_at_point(point.position) - a.velocity_at_point(point.position);
+                let tangent_velocity = relative_velocity - relative_velocity.dot(&normal) * normal;
 
-            let impulse_magnitude =
-                -(1.0 + restitution) * relative_velocity.dot(&collision.normal) / denominator;
+                let Some(tangent) = tangent_velocity.try_normalize(f64::EPSILON) else {
+                    continue;
+                };
 
-            a.linear.velocity -= (impulse_magnitude / a.linear.mass) * collision.normal;
-            a.angular.velocity -=
-                a_to_point.perp(&(impulse_magnitude * collision.normal)) / a.angular.inertia;
+                let tangent_denominator =
+                    DefaultCollisionResolver::denominator(a, b, &point.position, &tangent);
 
-            b.linear.velocity += (impulse_magnitude / b.linear.mass) * collision.normal;
-            b.angular.velocity +=
-                b_to_point.perp(&(impulse_magnitude * collision.normal)) / b.angular.inertia;
+                if tangent_denominator == 0.0 {
+                    continue;
+                }
 
-            // Positional correction
-            if collision.depth > self.correction_tolerance {
-                let correction = (collision.depth * self.correction_level * collision.normal)
-                    / (1.0 / a.linear.mass + 1.0 / b.linear.mass);
+                let tangent_magnitude = -relative_velocity.dot(&tangent) / tangent_denominator;
+                let normal_magnitude = point.normal_impulse.max(0.0);
+
+                // Sticks while inside the friction cone, otherwise slides with dynamic friction
+                let tangent_magnitude =
+                    if tangent_magnitude.abs() <= normal_magnitude * static_friction {
+                        tangent_magnitude
+                    } else {
+                        -normal_magnitude * dynamic_friction
+                    };
+                point.tangent_impulse = tangent_magnitude;
+
+                let tangent_impulse = tangent_magnitude * tangent;
+                a.apply_impulse_at_point(-tangent_impulse, point.position);
+                b.apply_impulse_at_point(tangent_impulse, point.position);
+            }
 
-                a.linear.position -= correction / a.linear.mass;
-                b.linear.position += correction / b.linear.mass;
+            // Positional correction
+            // Only bodies that can be moved are pushed apart
+            let (a_inverse_mass, b_inverse_mass) = (a.inverse_mass(), b.inverse_mass());
+            let inverse_mass_sum = a_inverse_mass + b_inverse_mass;
+            if collision.depth > self.correction_tolerance && inverse_mass_sum > 0.0 {
+                let correction =
+                    (collision.depth * self.correction_level * collision.normal) / inverse_mass_sum;
+
+                a.linear.position -= correction * a_inverse_mass;
+                b.linear.position += correction * b_inverse_mass;
             }
         }
     }
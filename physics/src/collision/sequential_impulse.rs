@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{collision::*, timestep::Pose};
+
+// Solves every contact point together over a number of iterations, keeping the total impulse
+// of each point clamped instead of each separate push
+#[derive(Serialize, Deserialize)]
+pub struct SequentialImpulseResolver {
+    pub iterations: u32,
+    // Start from the impulses the manifold kept from the last step so stacks settle quicker
+    pub warm_starting: bool,
+    // Approach speeds under this don't bounce, stops resting contacts jittering
+    pub restitution_threshold: f64,
+    position_iterations: u32,
+    correction_level: f64,
+    correction_tolerance: f64,
+}
+
+impl SequentialImpulseResolver {
+    pub fn new(iterations: u32) -> Self {
+        Self {
+            iterations,
+            warm_starting: true,
+            restitution_threshold: 5.0,
+            position_iterations: 3,
+            correction_level: 0.2,
+            correction_tolerance: 0.05,
+        }
+    }
+}
+
+impl Default for SequentialImpulseResolver {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+// A manifold point with everything the iterations need worked out up front
+struct SolverPoint {
+    collision: usize,
+    index: usize,
+    bodies: [BodyHandle; 2],
+    position: Vector<f64>,
+    depth: f64,
+    a_to_point: Vector<f64>,
+    b_to_point: Vector<f64>,
+    // Where the bodies were before any positional correction
+    a_start: Pose,
+    b_start: Pose,
+    normal: Vector<f64>,
+    tangent: Vector<f64>,
+    normal_mass: f64,
+    tangent_mass: f64,
+    velocity_bias: f64,
+    static_friction: f64,
+    dynamic_friction: f64,
+    normal_impulse: f64,
+    tangent_impulse: f64,
+}
+
+impl SolverPoint {
+    fn apply(&self, impulse: Vector<f64>, bodies: &mut IdMap<Body>) {
+        if let [Some(a), Some(b)] = bodies.get_disjoint_mut(self.bodies) {
+            a.apply_impulse_at_point(-impulse, self.position);
+            b.apply_impulse_at_point(impulse, self.position);
+        }
+    }
+
+    fn relative_velocity(&self, bodies: &IdMap<Body>) -> Vector<f64> {
+        match (bodies.get(self.bodies[0]), bodies.get(self.bodies[1])) {
+            (Some(a), Some(b)) => {
+                b.velocity_at_point(self.position) - a.velocity_at_point(self.position)
+            }
+            _ => Vector::zeros(),
+        }
+    }
+
+    // How far the body has moved the point since the start, small angles are close enough
+    fn moved(body: &Body, start: &Pose, to_point: &Vector<f64>) -> Vector<f64> {
+        let turned = body.angular.orientation - start.orientation;
+        body.linear.position - start.position + turned * Vector::new(-to_point.y, to_point.x)
+    }
+}
+
+impl SequentialImpulseResolver {
+    fn prepare(&self, collisions: &[CollisionData], bodies: &IdMap<Body>) -> Vec<SolverPoint> {
+        let mut points = Vec::new();
+
+        for (collision_index, collision) in collisions.iter().enumerate() {
+            if collision.sensor {
+                continue;
+            }
+
+            let (Some(a), Some(b)) = (
+                bodies.get(collision.bodies[0]),
+                bodies.get(collision.bodies[1]),
+            ) else {
+                continue;
+            };
+
+            let normal = collision.normal;
+            let tangent = Vector::new(normal.y, -normal.x);
+            let restitution = a.restitution * b.restitution;
+
+            for (index, point) in collision.manifold.points().iter().enumerate() {
+                let a_to_point = point.position - a.linear.position;
+                let b_to_point = point.position - b.linear.position;
+
+                let effective_mass = |direction: &Vector<f64>| {
+                    let inverse = a.inverse_mass()
+                        + b.inverse_mass()
+                        + a_to_point.perp(direction).powi(2) * a.inverse_inertia()
+                        + b_to_point.perp(direction).powi(2) * b.inverse_inertia();
+
+                    if inverse > 0.0 { 1.0 / inverse } else { 0.0 }
+                };
+
+                let normal_mass = effective_mass(&normal);
+
+                // Two bodies that can't move can't be pushed apart
+                if normal_mass == 0.0 {
+                    continue;
+                }
+
+                let relative_velocity =
+                    b.velocity_at_point(point.position) - a.velocity_at_point(point.position);
+                let normal_velocity = relative_velocity.dot(&normal);
+
+                let velocity_bias = if normal_velocity < -self.restitution_threshold {
+                    -restitution * normal_velocity
+                } else {
+                    0.0
+                };
+
+                let (normal_impulse, tangent_impulse) = if self.warm_starting {
+                    (point.normal_impulse, point.tangent_impulse)
+                } else {
+                    (0.0, 0.0)
+                };
+
+                points.push(SolverPoint {
+                    collision: collision_index,
+                    index,
+                    bodies: collision.bodies,
+                    position: point.position,
+                    depth: point.depth,
+                    a_to_point,
+                    b_to_point,
+                    a_start: Pose::new(a.linear.position, a.angular.orientation),
+                    b_start: Pose::new(b.linear.position, b.angular.orientation),
+                    normal,
+                    tangent,
+                    normal_mass,
+                    tangent_mass: effective_mass(&tangent),
+                    velocity_bias,
+                    static_friction: (a.static_friction * b.static_friction).sqrt(),
+                    dynamic_friction: (a.dynamic_friction * b.dynamic_friction).sqrt(),
+                    normal_impulse,
+                    tangent_impulse,
+                });
+            }
+        }
+
+        points
+    }
+
+    fn solve_point(point: &mut SolverPoint, bodies: &mut IdMap<Body>) {
+        // Friction first so the normal impulse has the final say on penetration
+        let relative_velocity = point.relative_velocity(bodies);
+        let lambda = -relative_velocity.dot(&point.tangent) * point.tangent_mass;
+
+        // Sticks inside the static cone and slides with dynamic friction once it breaks out
+        let max_static = point.static_friction * point.normal_impulse;
+        let mut tangent_impulse = point.tangent_impulse + lambda;
+        if tangent_impulse.abs() > max_static {
+            let max_dynamic = point.dynamic_friction * point.normal_impulse;
+            tangent_impulse = tangent_impulse.clamp(-max_dynamic, max_dynamic);
+        }
+
+        let change = tangent_impulse - point.tangent_impulse;
+        point.tangent_impulse = tangent_impulse;
+        point.apply(change * point.tangent, bodies);
+
+        let relative_velocity = point.relative_velocity(bodies);
+        let lambda =
+            (-relative_velocity.dot(&point.normal) + point.velocity_bias) * point.normal_mass;
+
+        // The total can only ever push the bodies apart
+        let normal_impulse = (point.normal_impulse + lambda).max(0.0);
+        let change = normal_impulse - point.normal_impulse;
+        point.normal_impulse = normal_impulse;
+        point.apply(change * point.normal, bodies);
+    }
+
+    // Pushes the point apart with a pseudo impulse, the rotation it adds is what lets a tilted
+    // box settle back onto both corners
+    fn correct_point(&self, point: &SolverPoint, bodies: &mut IdMap<Body>) {
+        let [Some(a), Some(b)] = bodies.get_disjoint_mut(point.bodies) else {
+            return;
+        };
+
+        let separated = (SolverPoint::moved(b, &point.b_start, &point.b_to_point)
+            - SolverPoint::moved(a, &point.a_start, &point.a_to_point))
+        .dot(&point.normal);
+        let depth = point.depth - separated - self.correction_tolerance;
+
+        if depth <= 0.0 {
+            return;
+        }
+
+        let correction = depth * self.correction_level * point.normal_mass * point.normal;
+
+        a.linear.position -= correction * a.inverse_mass();
+        a.angular.orientation -= point.a_to_point.perp(&correction) * a.inverse_inertia();
+        b.linear.position += correction * b.inverse_mass();
+        b.angular.orientation += point.b_to_point.perp(&correction) * b.inverse_inertia();
+    }
+}
+
+impl CollisionResolution for SequentialImpulseResolver {
+    fn init(&mut self, _bodies: &mut IdMap<Body>) {}
+
+    fn resolve(&mut self, collisions: &mut [CollisionData], bodies: &mut IdMap<Body>) {
+        let mut points = self.prepare(collisions, bodies);
+
+        for point in &points {
+            point.apply(
+                point.normal_impulse * point.normal + point.tangent_impulse * point.tangent,
+                bodies,
+            );
+        }
+
+        for _ in 0..self.iterations {
+            for point in points.iter_mut() {
+                SequentialImpulseResolver::solve_point(point, bodies);
+            }
+        }
+
+        // Kept on the manifold for events and warm starting the next step
+        for collision in collisions.iter_mut() {
+            for contact in collision.manifold.points_mut() {
+                contact.normal_impulse = 0.0;
+                contact.tangent_impulse = 0.0;
+            }
+        }
+
+        for point in &points {
+            let contact = &mut collisions[point.collision].manifold.points_mut()[point.index];
+            contact.normal_impulse = point.normal_impulse;
+            contact.tangent_impulse = point.tangent_impulse;
+        }
+
+        for _ in 0..self.position_iterations {
+            for point in &points {
+                self.correct_point(point, bodies);
+            }
+        }
+    }
+}
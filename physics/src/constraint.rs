@@ -0,0 +1,391 @@
+pub mod distance;
+pub mod prismatic;
+pub mod pulley;
+pub mod revolute;
+pub mod rope;
+pub mod weld;
+
+use std::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{Body, BodyHandle},
+    id_map::{Id, IdMap},
+    membership::BodyMembership,
+    types::{AsAny, math::*},
+    world::World,
+};
+
+// Hard constraints between two bodies, solved on velocities over a number of iterations and
+// then on positions for the error left over
+pub trait Constraint: Any + AsAny + BodyMembership {
+    // Also ties the bodies into one sleep island
+    fn bodies(&self) -> [BodyHandle; 2];
+
+    // Runs once a step before the iterations to work out what stays the same over them,
+    // and applies the last step's impulses when warm starting
+    fn prepare(&mut self, delta_time: f64, settings: &ConstraintSettings, bodies: &mut IdMap<Body>);
+
+    // One velocity iteration
+    fn solve(&mut self, bodies: &mut IdMap<Body>);
+
+    // One position iteration, moves the bodies straight back by part of the error. Keeping
+    // this out of the velocities stops warm starting from feeding old corrections back in
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>);
+
+    // Size of the impulse holding the anchors in place over the last step, motors aren't counted
+    // as they drive the joint rather than hold it together
+    fn reaction_impulse(&self) -> f64;
+
+    // Size of the angular impulse holding the angle between the bodies, kept apart from the
+    // linear impulse as it's in different units. Zero for joints that let the bodies turn
+    fn reaction_angular_impulse(&self) -> f64 {
+        0.0
+    }
+
+    // The world removes the joint when its reaction impulse goes over this
+    fn break_threshold(&self) -> Option<f64>;
+
+    // Same for the angular impulse
+    fn angular_break_threshold(&self) -> Option<f64> {
+        None
+    }
+}
+
+pub type BoxedConstraint = Box<dyn Constraint + Send + Sync>;
+pub type ConstraintHandle = Id<BoxedConstraint>;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConstraintSettings {
+    pub iterations: u32,
+    pub position_iterations: u32,
+    // Fraction of the position error taken out each position iteration
+    pub correction_level: f64,
+    pub warm_starting: bool,
+}
+
+impl ConstraintSettings {
+    pub fn new(iterations: u32, position_iterations: u32) -> Self {
+        Self {
+            iterations,
+            position_iterations,
+            correction_level: 1.0,
+            warm_starting: true,
+        }
+    }
+}
+
+impl Default for ConstraintSettings {
+    fn default() -> Self {
+        Self::new(10, 3)
+    }
+}
+
+// Range a joint's angle or translation is kept inside
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Limits {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Limits {
+    pub fn new(lower: f64, upper: f64) -> Self {
+        Self { lower, upper }
+    }
+}
+
+// Drives a joint towards a speed, the max force is a torque for joints that rotate
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Motor {
+    pub speed: f64,
+    pub max_force: f64,
+}
+
+impl Motor {
+    pub fn new(speed: f64, max_force: f64) -> Self {
+        Self { speed, max_force }
+    }
+}
+
+// Makes a joint act like a spring, frequency is in hertz and a damping ratio of one stops it
+// without bouncing
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Softness {
+    pub frequency: f64,
+    pub damping_ratio: f64,
+}
+
+impl Softness {
+    pub fn new(frequency: f64, damping_ratio: f64) -> Self {
+        Self {
+            frequency,
+            damping_ratio,
+        }
+    }
+
+    // Springs with no frequency would never pull back so they're treated as rigid
+    pub fn is_soft(&self) -> bool {
+        self.frequency > 0.0
+    }
+}
+
+// Sent when a joint breaks, the handle no longer points to anything
+#[derive(Clone, Debug)]
+pub struct ConstraintBreakEvent {
+    pub constraint: ConstraintHandle,
+    pub bodies: [BodyHandle; 2],
+    pub impulse: f64,
+    pub angular_impulse: f64,
+}
+
+// What every joint shares, they all hold two bodies and an optional break threshold. Joints
+// that hold an angle also take an angular threshold
+macro_rules! joint_basics {
+    ($joint:ty) => {
+        impl $joint {
+            pub fn with_break_threshold(mut self, threshold: f64) -> Self {
+                self.break_threshold = Some(threshold);
+                self
+            }
+        }
+
+        impl BodyMembership for $joint {
+            // A joint needs both of its bodies
+            fn body_removed(&mut self, body: BodyHandle) -> bool {
+                !self.bodies.contains(&body)
+            }
+        }
+    };
+    ($joint:ty, angular) => {
+        joint_basics!($joint);
+
+        impl $joint {
+            pub fn with_angular_break_threshold(mut self, threshold: f64) -> Self {
+                self.angular_break_threshold = Some(threshold);
+                self
+            }
+        }
+    };
+}
+
+pub(crate) use joint_basics;
+
+// Both of a joint's bodies, none if either isn't in the world
+pub(crate) fn world_bodies(world: &World, ids: [BodyHandle; 2]) -> Option<(&Body, &Body)> {
+    Some((world.get_body(ids[0])?, world.get_body(ids[1])?))
+}
+
+// A joint's bodies and where its anchors are now. Joints keep their anchors local, relative to
+// each body's position and turning with it, so they stay put on the body as it moves
+pub(crate) struct Anchors<'a> {
+    pub a: &'a Body,
+    pub b: &'a Body,
+    pub a_anchor: Vector<f64>,
+    pub b_anchor: Vector<f64>,
+    // From each body's position to its anchor, in world space
+    pub a_to_anchor: Vector<f64>,
+    pub b_to_anchor: Vector<f64>,
+}
+
+// None if either body has been removed
+pub(crate) fn anchors<'a>(
+    bodies: &'a IdMap<Body>,
+    ids: [BodyHandle; 2],
+    local_anchors: &[Vector<f64>; 2],
+) -> Option<Anchors<'a>> {
+    let (a, b) = (bodies.get(ids[0])?, bodies.get(ids[1])?);
+    let (a_anchor, b_anchor) = (
+        a.local_to_world(local_anchors[0]),
+        b.local_to_world(local_anchors[1]),
+    );
+
+    Some(Anchors {
+        a,
+        b,
+        a_anchor,
+        b_anchor,
+        a_to_anchor: a_anchor - a.linear.position,
+        b_to_anchor: b_anchor - b.linear.position,
+    })
+}
+
+// Inverse of how much the relative velocity along the direction changes for a unit impulse
+// at the anchors, zero if neither body can be moved
+pub(crate) fn effective_mass(
+    a: &Body,
+    b: &Body,
+    a_to_anchor: &Vector<f64>,
+    b_to_anchor: &Vector<f64>,
+    direction: &Vector<f64>,
+) -> f64 {
+    let inverse = a.inverse_mass()
+        + b.inverse_mass()
+        + a_to_anchor.perp(direction).powi(2) * a.inverse_inertia()
+        + b_to_anchor.perp(direction).powi(2) * b.inverse_inertia();
+
+    if inverse > 0.0 { 1.0 / inverse } else { 0.0 }
+}
+
+// Same as effective mass but for holding the anchors together in every direction at once,
+// none if neither body can be moved
+pub(crate) fn point_mass(
+    a: &Body,
+    b: &Body,
+    a_to_anchor: &Vector<f64>,
+    b_to_anchor: &Vector<f64>,
+) -> Option<Matrix<f64>> {
+    let (a_inverse_mass, b_inverse_mass) = (a.inverse_mass(), b.inverse_mass());
+    let (a_inverse_inertia, b_inverse_inertia) = (a.inverse_inertia(), b.inverse_inertia());
+    let (ra, rb) = (a_to_anchor, b_to_anchor);
+
+    let shared = -a_inverse_inertia * ra.x * ra.y - b_inverse_inertia * rb.x * rb.y;
+    let inverse = Matrix::new(
+        a_inverse_mass
+            + b_inverse_mass
+            + a_inverse_inertia * ra.y * ra.y
+            + b_inverse_inertia * rb.y * rb.y,
+        shared,
+        shared,
+        a_inverse_mass
+            + b_inverse_mass
+            + a_inverse_inertia * ra.x * ra.x
+            + b_inverse_inertia * rb.x * rb.x,
+    );
+
+    inverse.try_inverse()
+}
+
+// Pushes b with the impulse at its anchor and a against it at its own
+pub(crate) fn apply_impulse(
+    bodies: &mut IdMap<Body>,
+    ids: [BodyHandle; 2],
+    impulse: Vector<f64>,
+    a_to_anchor: &Vector<f64>,
+    b_to_anchor: &Vector<f64>,
+) {
+    if let [Some(a), Some(b)] = bodies.get_disjoint_mut(ids) {
+        let (a_anchor, b_anchor) = (
+            a.linear.position + a_to_anchor,
+            b.linear.position + b_to_anchor,
+        );
+        a.apply_impulse_at_point(-impulse, a_anchor);
+        b.apply_impulse_at_point(impulse, b_anchor);
+    }
+}
+
+// Velocity of b's anchor relative to a's
+pub(crate) fn relative_velocity(
+    bodies: &IdMap<Body>,
+    ids: [BodyHandle; 2],
+    a_to_anchor: &Vector<f64>,
+    b_to_anchor: &Vector<f64>,
+) -> Vector<f64> {
+    match (bodies.get(ids[0]), bodies.get(ids[1])) {
+        (Some(a), Some(b)) => {
+            b.velocity_at_point(b.linear.position + b_to_anchor)
+                - a.velocity_at_point(a.linear.position + a_to_anchor)
+        }
+        _ => Vector::zeros(),
+    }
+}
+
+// Inverse of how much the relative angular velocity changes for a unit angular impulse
+pub(crate) fn angular_mass(a: &Body, b: &Body) -> f64 {
+    let inverse = a.inverse_inertia() + b.inverse_inertia();
+
+    if inverse > 0.0 { 1.0 / inverse } else { 0.0 }
+}
+
+// Turns b with the angular impulse and a against it
+pub(crate) fn apply_angular_impulse(bodies: &mut IdMap<Body>, ids: [BodyHandle; 2], impulse: f64) {
+    if let [Some(a), Some(b)] = bodies.get_disjoint_mut(ids) {
+        a.apply_torque_impulse(-impulse);
+        b.apply_torque_impulse(impulse);
+    }
+}
+
+// Angular velocity of b relative to a
+pub(crate) fn relative_angular_velocity(bodies: &IdMap<Body>, ids: [BodyHandle; 2]) -> f64 {
+    match (bodies.get(ids[0]), bodies.get(ids[1])) {
+        (Some(a), Some(b)) => b.angular.velocity - a.angular.velocity,
+        _ => 0.0,
+    }
+}
+
+// Change in the accumulated impulse keeping a joint on one side of a limit, the error is
+// positive while inside it
+pub(crate) fn limit_impulse(
+    error: f64,
+    relative_velocity: f64,
+    mass: f64,
+    delta_time: f64,
+    accumulated: &mut f64,
+) -> f64 {
+    // Inside the limit it only stops the joint from going further than the limit this step,
+    // going past it is fixed on positions
+    let bias = error.max(0.0) / delta_time;
+
+    let lambda = -(relative_velocity + bias) * mass;
+    let total = (*accumulated + lambda).max(0.0);
+    let change = total - *accumulated;
+    *accumulated = total;
+
+    change
+}
+
+// Change in the accumulated impulse driving a joint towards the motor's speed
+pub(crate) fn motor_impulse(
+    motor: &Motor,
+    relative_velocity: f64,
+    mass: f64,
+    delta_time: f64,
+    accumulated: &mut f64,
+) -> f64 {
+    let lambda = -(relative_velocity - motor.speed) * mass;
+
+    // Can't use more force than it has over the step
+    let max_impulse = motor.max_force * delta_time;
+    let total = (*accumulated + lambda).clamp(-max_impulse, max_impulse);
+    let change = total - *accumulated;
+    *accumulated = total;
+
+    change
+}
+
+// Moves b by the impulse at its anchor and a against it at its own, for position iterations
+pub(crate) fn apply_position_impulse(
+    bodies: &mut IdMap<Body>,
+    ids: [BodyHandle; 2],
+    impulse: Vector<f64>,
+    a_to_anchor: &Vector<f64>,
+    b_to_anchor: &Vector<f64>,
+) {
+    if let [Some(a), Some(b)] = bodies.get_disjoint_mut(ids) {
+        a.linear.position -= impulse * a.inverse_mass();
+        a.angular.orientation -= a_to_anchor.perp(&impulse) * a.inverse_inertia();
+        b.linear.position += impulse * b.inverse_mass();
+        b.angular.orientation += b_to_anchor.perp(&impulse) * b.inverse_inertia();
+    }
+}
+
+// Turns b by the angular impulse and a against it, for position iterations
+pub(crate) fn apply_angular_position_impulse(
+    bodies: &mut IdMap<Body>,
+    ids: [BodyHandle; 2],
+    impulse: f64,
+) {
+    if let [Some(a), Some(b)] = bodies.get_disjoint_mut(ids) {
+        a.angular.orientation -= impulse * a.inverse_inertia();
+        b.angular.orientation += impulse * b.inverse_inertia();
+    }
+}
+
+// Moves the joint back inside a limit it went past, the error is positive while inside it
+pub(crate) fn limit_correction(error: f64, mass: f64, settings: &ConstraintSettings) -> f64 {
+    if error < 0.0 {
+        -settings.correction_level * error * mass
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::*, world::World};
+
+// Keeps two anchors a fixed length apart, like a rigid rod between the bodies
+#[derive(Serialize, Deserialize)]
+pub struct DistanceJoint {
+    pub bodies: [BodyHandle; 2],
+    pub local_anchors: [Vector<f64>; 2],
+    pub length: f64,
+    pub break_threshold: Option<f64>,
+    // Total over the iterations, kept for warm starting
+    impulse: f64,
+    #[serde(skip)]
+    prepared: Option<Prepared>,
+}
+
+struct Prepared {
+    a_to_anchor: Vector<f64>,
+    b_to_anchor: Vector<f64>,
+    direction: Vector<f64>,
+    mass: f64,
+}
+
+impl DistanceJoint {
+    pub fn new(bodies: [BodyHandle; 2], local_anchors: [Vector<f64>; 2], length: f64) -> Self {
+        Self {
+            bodies,
+            local_anchors,
+            length,
+            break_threshold: None,
+            impulse: 0.0,
+            prepared: None,
+        }
+    }
+
+    // Uses how far apart the anchors are now as the length
+    pub fn new_auto_length(
+        bodies: [BodyHandle; 2],
+        local_anchors: [Vector<f64>; 2],
+        world: &World,
+    ) -> Option<Self> {
+        let (a, b) = world_bodies(world, bodies)?;
+        let length = a
+            .local_to_world(local_anchors[0])
+            .metric_distance(&b.local_to_world(local_anchors[1]));
+
+        Some(Self::new(bodies, local_anchors, length))
+    }
+
+    pub fn impulse(&self) -> f64 {
+        self.impulse
+    }
+}
+
+joint_basics!(DistanceJoint);
+
+impl Constraint for DistanceJoint {
+    fn bodies(&self) -> [BodyHandle; 2] {
+        self.bodies
+    }
+
+    fn prepare(
+        &mut self,
+        _delta_time: f64,
+        settings: &ConstraintSettings,
+        bodies: &mut IdMap<Body>,
+    ) {
+        self.prepared = None;
+
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        // Anchors on top of each other have no direction to push along
+        let Some(direction) = (b_anchor - a_anchor).try_normalize(f64::EPSILON) else {
+            return;
+        };
+
+        let mass = effective_mass(a, b, &a_to_anchor, &b_to_anchor, &direction);
+
+        if mass == 0.0 {
+            return;
+        }
+
+        if !settings.warm_starting {
+            self.impulse = 0.0;
+        }
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            self.impulse * direction,
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+
+        self.prepared = Some(Prepared {
+            a_to_anchor,
+            b_to_anchor,
+            direction,
+            mass,
+        });
+    }
+
+    fn solve(&mut self, bodies: &mut IdMap<Body>) {
+        let Some(prepared) = &self.prepared else {
+            return;
+        };
+
+        let velocity = relative_velocity(
+            bodies,
+            self.bodies,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        )
+        .dot(&prepared.direction);
+
+        // Can pull and push so the total isn't clamped
+        let lambda = -velocity * prepared.mass;
+        self.impulse += lambda;
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            lambda * prepared.direction,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+    }
+
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>) {
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let separation = b_anchor - a_anchor;
+
+        let Some(direction) = separation.try_normalize(f64::EPSILON) else {
+            return;
+        };
+
+        let mass = effective_mass(a, b, &a_to_anchor, &b_to_anchor, &direction);
+        let error = separation.norm() - self.length;
+
+        apply_position_impulse(
+            bodies,
+            self.bodies,
+            -settings.correction_level * error * mass * direction,
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+    }
+
+    fn reaction_impulse(&self) -> f64 {
+        self.impulse.abs()
+    }
+
+    fn break_threshold(&self) -> Option<f64> {
+        self.break_threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{AngularState, BodyType, LinearState, Shape},
+        effector::ConstantAcceleration,
+        integrator::SemiImplicitEuler,
+    };
+
+    #[test]
+    fn pendulum_holds_its_length() {
+        let mut world = World::new();
+        let pivot = world.add_body(
+            Body::new_particle(
+                LinearState::new(Vector::zeros(), Vector::zeros(), f64::INFINITY),
+                0.5,
+            )
+            .with_body_type(BodyType::Static),
+        );
+        let bob = world.add_body(Body::new_rigid(
+            LinearState::new(Vector::new(100.0, 0.0), Vector::zeros(), 1.0),
+            0.5,
+            AngularState::new(0.0, 0.0, 100.0),
+            Shape::new_circle(10.0),
+        ));
+
+        world.add_integrator(Box::new(SemiImplicitEuler::new(vec![bob])));
+        world.add_effector(Box::new(ConstantAcceleration::new(
+            vec![bob],
+            Vector::new(0.0, -200.0),
+        )));
+        let joint = DistanceJoint::new_auto_length([pivot, bob], [Vector::zeros(); 2], &world);
+        world.add_constraint(Box::new(joint.unwrap()));
+
+        let mut lowest: f64 = 0.0;
+
+        for _ in 0..120 {
+            world.update(1.0 / 60.0);
+
+            let position = world.get_body(bob).unwrap().linear.position;
+            lowest = lowest.min(position.y);
+            assert!((position.norm() - 100.0).abs() < 0.5, "{}", position.norm());
+        }
+
+        // It swung down rather than being held in place
+        assert!(lowest < -90.0, "{lowest}");
+    }
+}
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::*, world::World};
+
+// Lets b slide along an axis fixed to a without turning relative to it, like a piston or a
+// sliding door
+#[derive(Serialize, Deserialize)]
+pub struct PrismaticJoint {
+    pub bodies: [BodyHandle; 2],
+    pub local_anchors: [Vector<f64>; 2],
+    // Direction b slides in, turning with a
+    pub local_axis: Vector<f64>,
+    // Angle of b relative to a that's held
+    pub reference_angle: f64,
+    // Translations are how far b's anchor is from a's along the axis
+    pub limits: Option<Limits>,
+    pub motor: Option<Motor>,
+    pub break_threshold: Option<f64>,
+    pub angular_break_threshold: Option<f64>,
+    // Totals over the iterations, kept for warm starting. The first part of the impulse is
+    // across the axis and the second turns the bodies
+    impulse: Vector<f64>,
+    motor_impulse: f64,
+    lower_impulse: f64,
+    upper_impulse: f64,
+    #[serde(skip)]
+    prepared: Option<Prepared>,
+}
+
+struct Prepared {
+    // From a's position to b's anchor so the arms line up on the axis
+    a_to_anchor: Vector<f64>,
+    b_to_anchor: Vector<f64>,
+    axis: Vector<f64>,
+    normal: Vector<f64>,
+    mass: Matrix<f64>,
+    axial_mass: f64,
+    // Across the axis and in angle
+    error: Vector<f64>,
+    translation: f64,
+    delta_time: f64,
+}
+
+impl PrismaticJoint {
+    pub fn new(
+        bodies: [BodyHandle; 2],
+        local_anchors: [Vector<f64>; 2],
+        local_axis: Vector<f64>,
+        reference_angle: f64,
+    ) -> Self {
+        Self {
+            bodies,
+            local_anchors,
+            local_axis,
+            reference_angle,
+            limits: None,
+            motor: None,
+            break_threshold: None,
+            angular_break_threshold: None,
+            impulse: Vector::zeros(),
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+            prepared: None,
+        }
+    }
+
+    // Slides the bodies from where they are now along an axis in world space
+    pub fn new_at_point(
+        bodies: [BodyHandle; 2],
+        anchor: Vector<f64>,
+        axis: Vector<f64>,
+        world: &World,
+    ) -> Option<Self> {
+        let (a, b) = world_bodies(world, bodies)?;
+
+        Some(Self::new(
+            bodies,
+            [a.world_to_local(anchor), b.world_to_local(anchor)],
+            Rotation::new(-a.angular.orientation) * axis,
+            b.angular.orientation - a.angular.orientation,
+        ))
+    }
+
+    pub fn with_limits(mut self, lower: f64, upper: f64) -> Self {
+        self.limits = Some(Limits::new(lower, upper));
+        self
+    }
+
+    pub fn with_motor(mut self, speed: f64, max_force: f64) -> Self {
+        self.motor = Some(Motor::new(speed, max_force));
+        self
+    }
+
+    pub fn impulse(&self) -> Vector<f64> {
+        self.impulse
+    }
+
+    pub fn motor_impulse(&self) -> f64 {
+        self.motor_impulse
+    }
+
+    fn axial_impulse(&self) -> f64 {
+        self.motor_impulse + self.lower_impulse - self.upper_impulse
+    }
+
+    // Axis, masses and errors for where the bodies are now, none if a body is missing, the axis
+    // has no length or neither body can be moved
+    fn measure(&self, bodies: &IdMap<Body>, delta_time: f64) -> Option<Prepared> {
+        let local_axis = self.local_axis.try_normalize(f64::EPSILON)?;
+        let Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            b_to_anchor,
+            ..
+        } = anchors(bodies, self.bodies, &self.local_anchors)?;
+
+        let axis = Rotation::new(a.angular.orientation) * local_axis;
+        let normal = Vector::new(-axis.y, axis.x);
+
+        let separation = b_anchor - a_anchor;
+        let a_to_anchor = b_anchor - a.linear.position;
+
+        // Holding b on the axis and at the same angle is solved as one block
+        let (a_inverse_inertia, b_inverse_inertia) = (a.inverse_inertia(), b.inverse_inertia());
+        let (a_arm, b_arm) = (a_to_anchor.perp(&normal), b_to_anchor.perp(&normal));
+        let shared = a_inverse_inertia * a_arm + b_inverse_inertia * b_arm;
+        let angular = a_inverse_inertia + b_inverse_inertia;
+
+        let inverse = Matrix::new(
+            a.inverse_mass()
+                + b.inverse_mass()
+                + a_inverse_inertia * a_arm * a_arm
+                + b_inverse_inertia * b_arm * b_arm,
+            shared,
+            shared,
+            // Bodies that can't turn still need something to invert
+            if angular > 0.0 { angular } else { 1.0 },
+        );
+
+        Some(Prepared {
+            a_to_anchor,
+            b_to_anchor,
+            axis,
+            normal,
+            mass: inverse.try_inverse()?,
+            axial_mass: effective_mass(a, b, &a_to_anchor, &b_to_anchor, &axis),
+            error: Vector::new(
+                separation.dot(&normal),
+                b.angular.orientation - a.angular.orientation - self.reference_angle,
+            ),
+            translation: separation.dot(&axis),
+            delta_time,
+        })
+    }
+}
+
+joint_basics!(PrismaticJoint, angular);
+
+impl Constraint for PrismaticJoint {
+    fn bodies(&self) -> [BodyHandle; 2] {
+        self.bodies
+    }
+
+    fn prepare(
+        &mut self,
+        delta_time: f64,
+        settings: &ConstraintSettings,
+        bodies: &mut IdMap<Body>,
+    ) {
+        self.prepared = None;
+
+        let Some(prepared) = self.measure(bodies, delta_time) else {
+            return;
+        };
+
+        if !settings.warm_starting {
+            self.impulse = Vector::zeros();
+            self.motor_impulse = 0.0;
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+
+        // Dropped once the motor or limits are turned off, or when nothing can slide along the axis
+        if self.motor.is_none() || prepared.axial_mass == 0.0 {
+            self.motor_impulse = 0.0;
+        }
+
+        if self.limits.is_none() || prepared.axial_mass == 0.0 {
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            self.impulse.x * prepared.normal + self.axial_impulse() * prepared.axis,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+        apply_angular_impulse(bodies, self.bodies, self.impulse.y);
+
+        self.prepared = Some(prepared);
+    }
+
+    fn solve(&mut self, bodies: &mut IdMap<Body>) {
+        let Some(prepared) = &self.prepared else {
+            return;
+        };
+
+        let axial_velocity = |bodies: &IdMap<Body>| {
+            relative_velocity(
+                bodies,
+                self.bodies,
+                &prepared.a_to_anchor,
+                &prepared.b_to_anchor,
+            )
+            .dot(&prepared.axis)
+        };
+
+        if prepared.axial_mass > 0.0 {
+            if let Some(motor) = self.motor {
+                let change = motor_impulse(
+                    &motor,
+                    axial_velocity(bodies),
+                    prepared.axial_mass,
+                    prepared.delta_time,
+                    &mut self.motor_impulse,
+                );
+                apply_impulse(
+                    bodies,
+                    self.bodies,
+                    change * prepared.axis,
+                    &prepared.a_to_anchor,
+                    &prepared.b_to_anchor,
+                );
+            }
+
+            if let Some(limits) = self.limits {
+                let change = limit_impulse(
+                    prepared.translation - limits.lower,
+                    axial_velocity(bodies),
+                    prepared.axial_mass,
+                    prepared.delta_time,
+                    &mut self.lower_impulse,
+                );
+                apply_impulse(
+                    bodies,
+                    self.bodies,
+                    change * prepared.axis,
+                    &prepared.a_to_anchor,
+                    &prepared.b_to_anchor,
+                );
+
+                // The upper limit pushes the other way
+                let change = limit_impulse(
+                    limits.upper - prepared.translation,
+                    -axial_velocity(bodies),
+                    prepared.axial_mass,
+                    prepared.delta_time,
+                    &mut self.upper_impulse,
+                );
+                apply_impulse(
+                    bodies,
+                    self.bodies,
+                    -change * prepared.axis,
+                    &prepared.a_to_anchor,
+                    &prepared.b_to_anchor,
+                );
+            }
+        }
+
+        // Holding b on the axis goes last so it wins over the rest
+        let velocity = Vector::new(
+            relative_velocity(
+                bodies,
+                self.bodies,
+                &prepared.a_to_anchor,
+                &prepared.b_to_anchor,
+            )
+            .dot(&prepared.normal),
+            relative_angular_velocity(bodies, self.bodies),
+        );
+        let lambda = -(prepared.mass * velocity);
+        self.impulse += lambda;
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            lambda.x * prepared.normal,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+        apply_angular_impulse(bodies, self.bodies, lambda.y);
+    }
+
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>) {
+        // The step's length only matters to the velocities
+        let Some(measured) = self.measure(bodies, 0.0) else {
+            return;
+        };
+
+        if let Some(limits) = self.limits {
+            let correction = limit_correction(
+                measured.translation - limits.lower,
+                measured.axial_mass,
+                settings,
+            ) - limit_correction(
+                limits.upper - measured.translation,
+                measured.axial_mass,
+                settings,
+            );
+
+            apply_position_impulse(
+                bodies,
+                self.bodies,
+                correction * measured.axis,
+                &measured.a_to_anchor,
+                &measured.b_to_anchor,
+            );
+        }
+
+        let correction = -settings.correction_level * (measured.mass * measured.error);
+
+        apply_position_impulse(
+            bodies,
+            self.bodies,
+            correction.x * measured.normal,
+            &measured.a_to_anchor,
+            &measured.b_to_anchor,
+        );
+        apply_angular_position_impulse(bodies, self.bodies, correction.y);
+    }
+
+    fn reaction_impulse(&self) -> f64 {
+        Vector::new(self.impulse.x, self.lower_impulse - self.upper_impulse).norm()
+    }
+
+    fn reaction_angular_impulse(&self) -> f64 {
+        self.impulse.y.abs()
+    }
+
+    fn break_threshold(&self) -> Option<f64> {
+        self.break_threshold
+    }
+
+    fn angular_break_threshold(&self) -> Option<f64> {
+        self.angular_break_threshold
+    }
+}
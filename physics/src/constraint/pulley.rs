@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::*, world::World};
+
+// Hangs two bodies from fixed points in the world on one rope, so one going down pulls the
+// other up. The ratio gears b's side like a block and tackle, b's side moves 1 / ratio for
+// every unit a's side moves
+#[derive(Serialize, Deserialize)]
+pub struct PulleyJoint {
+    pub bodies: [BodyHandle; 2],
+    // The points each side of the rope runs over, in world space
+    pub ground_anchors: [Vector<f64>; 2],
+    pub local_anchors: [Vector<f64>; 2],
+    pub ratio: f64,
+    // Longest a's side plus the ratio times b's side can be, the rope goes slack under it
+    pub length: f64,
+    pub break_threshold: Option<f64>,
+    // Total tension over the iterations, kept for warm starting
+    impulse: f64,
+    #[serde(skip)]
+    prepared: Option<Prepared>,
+}
+
+struct Prepared {
+    a_to_anchor: Vector<f64>,
+    b_to_anchor: Vector<f64>,
+    // From each ground anchor out to the body's anchor
+    directions: [Vector<f64>; 2],
+    ratio: f64,
+    mass: f64,
+    slack: f64,
+    delta_time: f64,
+}
+
+impl PulleyJoint {
+    pub fn new(
+        bodies: [BodyHandle; 2],
+        ground_anchors: [Vector<f64>; 2],
+        local_anchors: [Vector<f64>; 2],
+        ratio: f64,
+        length: f64,
+    ) -> Self {
+        Self {
+            bodies,
+            ground_anchors,
+            local_anchors,
+            ratio,
+            length,
+            break_threshold: None,
+            impulse: 0.0,
+            prepared: None,
+        }
+    }
+
+    // Uses how much rope is out now as the length so it starts tight
+    pub fn new_auto_length(
+        bodies: [BodyHandle; 2],
+        ground_anchors: [Vector<f64>; 2],
+        local_anchors: [Vector<f64>; 2],
+        ratio: f64,
+        world: &World,
+    ) -> Option<Self> {
+        let (a, b) = world_bodies(world, bodies)?;
+        let length = a
+            .local_to_world(local_anchors[0])
+            .metric_distance(&ground_anchors[0])
+            + ratio
+                * b.local_to_world(local_anchors[1])
+                    .metric_distance(&ground_anchors[1]);
+
+        Some(Self::new(
+            bodies,
+            ground_anchors,
+            local_anchors,
+            ratio,
+            length,
+        ))
+    }
+
+    pub fn impulse(&self) -> f64 {
+        self.impulse
+    }
+
+    // Rope directions, mass and slack for where the bodies are now, none if a body is missing,
+    // sits on its ground anchor or neither body can be moved
+    fn measure(&self, bodies: &IdMap<Body>, delta_time: f64) -> Option<Prepared> {
+        let Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        } = anchors(bodies, self.bodies, &self.local_anchors)?;
+        let (a_rope, b_rope) = (
+            a_anchor - self.ground_anchors[0],
+            b_anchor - self.ground_anchors[1],
+        );
+
+        let directions = [
+            a_rope.try_normalize(f64::EPSILON)?,
+            b_rope.try_normalize(f64::EPSILON)?,
+        ];
+
+        let inverse = a.inverse_mass()
+            + a_to_anchor.perp(&directions[0]).powi(2) * a.inverse_inertia()
+            + self.ratio.powi(2)
+                * (b.inverse_mass()
+                    + b_to_anchor.perp(&directions[1]).powi(2) * b.inverse_inertia());
+
+        if inverse <= 0.0 {
+            return None;
+        }
+
+        Some(Prepared {
+            a_to_anchor,
+            b_to_anchor,
+            directions,
+            ratio: self.ratio,
+            mass: 1.0 / inverse,
+            slack: self.length - a_rope.norm() - self.ratio * b_rope.norm(),
+            delta_time,
+        })
+    }
+}
+
+joint_basics!(PulleyJoint);
+
+impl Constraint for PulleyJoint {
+    fn bodies(&self) -> [BodyHandle; 2] {
+        self.bodies
+    }
+
+    fn prepare(
+        &mut self,
+        delta_time: f64,
+        settings: &ConstraintSettings,
+        bodies: &mut IdMap<Body>,
+    ) {
+        self.prepared = None;
+
+        let Some(prepared) = self.measure(bodies, delta_time) else {
+            return;
+        };
+
+        if !settings.warm_starting {
+            self.impulse = 0.0;
+        }
+
+        apply_tension(bodies, self.bodies, &prepared, self.impulse);
+        self.prepared = Some(prepared);
+    }
+
+    fn solve(&mut self, bodies: &mut IdMap<Body>) {
+        let Some(prepared) = &self.prepared else {
+            return;
+        };
+
+        // A rope only pulls, so it works like the upper limit of the length
+        let change = limit_impulse(
+            prepared.slack,
+            -lengthening(bodies, self.bodies, prepared),
+            prepared.mass,
+            prepared.delta_time,
+            &mut self.impulse,
+        );
+
+        apply_tension(bodies, self.bodies, prepared, change);
+    }
+
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>) {
+        // Only the slack is corrected here so the step's length isn't needed
+        let Some(measured) = self.measure(bodies, 0.0) else {
+            return;
+        };
+
+        let correction = limit_correction(measured.slack, measured.mass, settings);
+
+        if let [Some(a), Some(b)] = bodies.get_disjoint_mut(self.bodies) {
+            let (a_push, b_push) = (
+                -correction * measured.directions[0],
+                -correction * measured.ratio * measured.directions[1],
+            );
+
+            a.linear.position += a_push * a.inverse_mass();
+            a.angular.orientation += measured.a_to_anchor.perp(&a_push) * a.inverse_inertia();
+            b.linear.position += b_push * b.inverse_mass();
+            b.angular.orientation += measured.b_to_anchor.perp(&b_push) * b.inverse_inertia();
+        }
+    }
+
+    fn reaction_impulse(&self) -> f64 {
+        self.impulse
+    }
+
+    fn break_threshold(&self) -> Option<f64> {
+        self.break_threshold
+    }
+}
+
+// Pulls both bodies towards their ground anchors with the tension, b's side geared by
+// the ratio
+fn apply_tension(
+    bodies: &mut IdMap<Body>,
+    ids: [BodyHandle; 2],
+    prepared: &Prepared,
+    tension: f64,
+) {
+    if let [Some(a), Some(b)] = bodies.get_disjoint_mut(ids) {
+        let (a_anchor, b_anchor) = (
+            a.linear.position + prepared.a_to_anchor,
+            b.linear.position + prepared.b_to_anchor,
+        );
+        a.apply_impulse_at_point(-tension * prepared.directions[0], a_anchor);
+        b.apply_impulse_at_point(-tension * prepared.ratio * prepared.directions[1], b_anchor);
+    }
+}
+
+// How fast the rope is being pulled out
+fn lengthening(bodies: &IdMap<Body>, ids: [BodyHandle; 2], prepared: &Prepared) -> f64 {
+    match (bodies.get(ids[0]), bodies.get(ids[1])) {
+        (Some(a), Some(b)) => {
+            a.velocity_at_point(a.linear.position + prepared.a_to_anchor)
+                .dot(&prepared.directions[0])
+                + prepared.ratio
+                    * b.velocity_at_point(b.linear.position + prepared.b_to_anchor)
+                        .dot(&prepared.directions[1])
+        }
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::*, world::World};
+
+// Pins two bodies together at an anchor they share while letting them turn freely, like a
+// hinge or an axle
+#[derive(Serialize, Deserialize)]
+pub struct RevoluteJoint {
+    pub bodies: [BodyHandle; 2],
+    pub local_anchors: [Vector<f64>; 2],
+    // Angle of b relative to a that counts as zero for the limits
+    pub reference_angle: f64,
+    pub limits: Option<Limits>,
+    pub motor: Option<Motor>,
+    pub break_threshold: Option<f64>,
+    pub angular_break_threshold: Option<f64>,
+    // Totals over the iterations, kept for warm starting
+    impulse: Vector<f64>,
+    motor_impulse: f64,
+    lower_impulse: f64,
+    upper_impulse: f64,
+    #[serde(skip)]
+    prepared: Option<Prepared>,
+}
+
+struct Prepared {
+    a_to_anchor: Vector<f64>,
+    b_to_anchor: Vector<f64>,
+    mass: Matrix<f64>,
+    angular_mass: f64,
+    angle: f64,
+    delta_time: f64,
+}
+
+impl RevoluteJoint {
+    pub fn new(
+        bodies: [BodyHandle; 2],
+        local_anchors: [Vector<f64>; 2],
+        reference_angle: f64,
+    ) -> Self {
+        Self {
+            bodies,
+            local_anchors,
+            reference_angle,
+            limits: None,
+            motor: None,
+            break_threshold: None,
+            angular_break_threshold: None,
+            impulse: Vector::zeros(),
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+            prepared: None,
+        }
+    }
+
+    // Pins the bodies where they are now at a point in world space
+    pub fn new_at_point(
+        bodies: [BodyHandle; 2],
+        anchor: Vector<f64>,
+        world: &World,
+    ) -> Option<Self> {
+        let (a, b) = world_bodies(world, bodies)?;
+
+        Some(Self::new(
+            bodies,
+            [a.world_to_local(anchor), b.world_to_local(anchor)],
+            b.angular.orientation - a.angular.orientation,
+        ))
+    }
+
+    // Angles are relative to the reference angle
+    pub fn with_limits(mut self, lower: f64, upper: f64) -> Self {
+        self.limits = Some(Limits::new(lower, upper));
+        self
+    }
+
+    pub fn with_motor(mut self, speed: f64, max_torque: f64) -> Self {
+        self.motor = Some(Motor::new(speed, max_torque));
+        self
+    }
+
+    pub fn impulse(&self) -> Vector<f64> {
+        self.impulse
+    }
+
+    pub fn motor_impulse(&self) -> f64 {
+        self.motor_impulse
+    }
+
+    fn angular_impulse(&self) -> f64 {
+        self.motor_impulse + self.lower_impulse - self.upper_impulse
+    }
+}
+
+joint_basics!(RevoluteJoint, angular);
+
+impl Constraint for RevoluteJoint {
+    fn bodies(&self) -> [BodyHandle; 2] {
+        self.bodies
+    }
+
+    fn prepare(
+        &mut self,
+        delta_time: f64,
+        settings: &ConstraintSettings,
+        bodies: &mut IdMap<Body>,
+    ) {
+        self.prepared = None;
+
+        let Some(Anchors {
+            a,
+            b,
+            a_to_anchor,
+            b_to_anchor,
+            ..
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let Some(mass) = point_mass(a, b, &a_to_anchor, &b_to_anchor) else {
+            return;
+        };
+
+        let angular_mass = angular_mass(a, b);
+        let angle = b.angular.orientation - a.angular.orientation - self.reference_angle;
+
+        if !settings.warm_starting {
+            self.impulse = Vector::zeros();
+            self.motor_impulse = 0.0;
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+
+        // Turned off parts shouldn't keep pushing from an old step
+        if self.motor.is_none() || angular_mass == 0.0 {
+            self.motor_impulse = 0.0;
+        }
+
+        if self.limits.is_none() || angular_mass == 0.0 {
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            self.impulse,
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+        apply_angular_impulse(bodies, self.bodies, self.angular_impulse());
+
+        self.prepared = Some(Prepared {
+            a_to_anchor,
+            b_to_anchor,
+            mass,
+            angular_mass,
+            angle,
+            delta_time,
+        });
+    }
+
+    fn solve(&mut self, bodies: &mut IdMap<Body>) {
+        let Some(prepared) = &self.prepared else {
+            return;
+        };
+
+        if prepared.angular_mass > 0.0 {
+            if let Some(motor) = self.motor {
+                let change = motor_impulse(
+                    &motor,
+                    relative_angular_velocity(bodies, self.bodies),
+                    prepared.angular_mass,
+                    prepared.delta_time,
+                    &mut self.motor_impulse,
+                );
+                apply_angular_impulse(bodies, self.bodies, change);
+            }
+
+            if let Some(limits) = self.limits {
+                let change = limit_impulse(
+                    prepared.angle - limits.lower,
+                    relative_angular_velocity(bodies, self.bodies),
+                    prepared.angular_mass,
+                    prepared.delta_time,
+                    &mut self.lower_impulse,
+                );
+                apply_angular_impulse(bodies, self.bodies, change);
+
+                // The upper limit pushes the other way
+                let change = limit_impulse(
+                    limits.upper - prepared.angle,
+                    -relative_angular_velocity(bodies, self.bodies),
+                    prepared.angular_mass,
+                    prepared.delta_time,
+                    &mut self.upper_impulse,
+                );
+                apply_angular_impulse(bodies, self.bodies, -change);
+            }
+        }
+
+        // Holding the anchors together goes last so it wins over the rest
+        let velocity = relative_velocity(
+            bodies,
+            self.bodies,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+        let lambda = -(prepared.mass * velocity);
+        self.impulse += lambda;
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            lambda,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+    }
+
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>) {
+        let (Some(a), Some(b)) = (bodies.get(self.bodies[0]), bodies.get(self.bodies[1])) else {
+            return;
+        };
+
+        if let Some(limits) = self.limits {
+            let mass = angular_mass(a, b);
+            let angle = b.angular.orientation - a.angular.orientation - self.reference_angle;
+            let correction = limit_correction(angle - limits.lower, mass, settings)
+                - limit_correction(limits.upper - angle, mass, settings);
+
+            apply_angular_position_impulse(bodies, self.bodies, correction);
+        }
+
+        // The limits moved the bodies so the anchors are found again
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let Some(mass) = point_mass(a, b, &a_to_anchor, &b_to_anchor) else {
+            return;
+        };
+
+        apply_position_impulse(
+            bodies,
+            self.bodies,
+            -settings.correction_level * (mass * (b_anchor - a_anchor)),
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+    }
+
+    fn reaction_impulse(&self) -> f64 {
+        self.impulse.norm()
+    }
+
+    fn reaction_angular_impulse(&self) -> f64 {
+        (self.lower_impulse - self.upper_impulse).abs()
+    }
+
+    fn break_threshold(&self) -> Option<f64> {
+        self.break_threshold
+    }
+
+    fn angular_break_threshold(&self) -> Option<f64> {
+        self.angular_break_threshold
+    }
+}
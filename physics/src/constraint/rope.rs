@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::*, world::World};
+
+// Stops two anchors from getting further apart than a length but lets them come together
+// freely, like a rope tied between the bodies
+#[derive(Serialize, Deserialize)]
+pub struct RopeJoint {
+    pub bodies: [BodyHandle; 2],
+    pub local_anchors: [Vector<f64>; 2],
+    pub max_length: f64,
+    pub break_threshold: Option<f64>,
+    // Total tension over the iterations, kept for warm starting
+    impulse: f64,
+    #[serde(skip)]
+    prepared: Option<Prepared>,
+}
+
+struct Prepared {
+    a_to_anchor: Vector<f64>,
+    b_to_anchor: Vector<f64>,
+    direction: Vector<f64>,
+    mass: f64,
+    // How much the rope can still stretch before it's tight
+    slack: f64,
+    delta_time: f64,
+}
+
+impl RopeJoint {
+    pub fn new(bodies: [BodyHandle; 2], local_anchors: [Vector<f64>; 2], max_length: f64) -> Self {
+        Self {
+            bodies,
+            local_anchors,
+            max_length,
+            break_threshold: None,
+            impulse: 0.0,
+            prepared: None,
+        }
+    }
+
+    // Uses how far apart the anchors are now as the max length so the rope starts tight
+    pub fn new_auto_length(
+        bodies: [BodyHandle; 2],
+        local_anchors: [Vector<f64>; 2],
+        world: &World,
+    ) -> Option<Self> {
+        let (a, b) = world_bodies(world, bodies)?;
+        let max_length = a
+            .local_to_world(local_anchors[0])
+            .metric_distance(&b.local_to_world(local_anchors[1]));
+
+        Some(Self::new(bodies, local_anchors, max_length))
+    }
+
+    pub fn impulse(&self) -> f64 {
+        self.impulse
+    }
+}
+
+joint_basics!(RopeJoint);
+
+impl Constraint for RopeJoint {
+    fn bodies(&self) -> [BodyHandle; 2] {
+        self.bodies
+    }
+
+    fn prepare(
+        &mut self,
+        delta_time: f64,
+        settings: &ConstraintSettings,
+        bodies: &mut IdMap<Body>,
+    ) {
+        self.prepared = None;
+
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let separation = b_anchor - a_anchor;
+
+        // Anchors on top of each other have no direction to pull along
+        let Some(direction) = separation.try_normalize(f64::EPSILON) else {
+            return;
+        };
+
+        let mass = effective_mass(a, b, &a_to_anchor, &b_to_anchor, &direction);
+
+        if mass == 0.0 {
+            return;
+        }
+
+        if !settings.warm_starting {
+            self.impulse = 0.0;
+        }
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            -self.impulse * direction,
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+
+        self.prepared = Some(Prepared {
+            a_to_anchor,
+            b_to_anchor,
+            direction,
+            mass,
+            slack: self.max_length - separation.norm(),
+            delta_time,
+        });
+    }
+
+    fn solve(&mut self, bodies: &mut IdMap<Body>) {
+        let Some(prepared) = &self.prepared else {
+            return;
+        };
+
+        let velocity = relative_velocity(
+            bodies,
+            self.bodies,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        )
+        .dot(&prepared.direction);
+
+        // Only pulls, so it works like the upper limit of a distance
+        let change = limit_impulse(
+            prepared.slack,
+            -velocity,
+            prepared.mass,
+            prepared.delta_time,
+            &mut self.impulse,
+        );
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            -change * prepared.direction,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+    }
+
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>) {
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let separation = b_anchor - a_anchor;
+
+        let Some(direction) = separation.try_normalize(f64::EPSILON) else {
+            return;
+        };
+
+        let mass = effective_mass(a, b, &a_to_anchor, &b_to_anchor, &direction);
+        let correction = limit_correction(self.max_length - separation.norm(), mass, settings);
+
+        apply_position_impulse(
+            bodies,
+            self.bodies,
+            -correction * direction,
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+    }
+
+    fn reaction_impulse(&self) -> f64 {
+        self.impulse
+    }
+
+    fn break_threshold(&self) -> Option<f64> {
+        self.break_threshold
+    }
+}
@@ -0,0 +1,263 @@
+use std::f64;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::*, world::World};
+
+// Locks two bodies together at an anchor so they move and turn as one, or flex back into
+// place when soft
+#[derive(Serialize, Deserialize)]
+pub struct WeldJoint {
+    pub bodies: [BodyHandle; 2],
+    pub local_anchors: [Vector<f64>; 2],
+    // Angle of b relative to a that's held
+    pub reference_angle: f64,
+    pub softness: Option<Softness>,
+    pub break_threshold: Option<f64>,
+    pub angular_break_threshold: Option<f64>,
+    // Totals over the iterations, kept for warm starting
+    impulse: Vector<f64>,
+    angular_impulse: f64,
+    #[serde(skip)]
+    prepared: Option<Prepared>,
+}
+
+struct Prepared {
+    a_to_anchor: Vector<f64>,
+    b_to_anchor: Vector<f64>,
+    mass: Matrix<f64>,
+    angular_mass: f64,
+    error: Vector<f64>,
+    angle_error: f64,
+    // How much of the error is fed back, how much of the full impulse is used and how much
+    // of the total is let go each iteration, a rigid weld uses all of the impulse
+    bias_rate: f64,
+    mass_scale: f64,
+    impulse_scale: f64,
+}
+
+impl WeldJoint {
+    pub fn new(
+        bodies: [BodyHandle; 2],
+        local_anchors: [Vector<f64>; 2],
+        reference_angle: f64,
+    ) -> Self {
+        Self {
+            bodies,
+            local_anchors,
+            reference_angle,
+            softness: None,
+            break_threshold: None,
+            angular_break_threshold: None,
+            impulse: Vector::zeros(),
+            angular_impulse: 0.0,
+            prepared: None,
+        }
+    }
+
+    // Welds the bodies where they are now at a point in world space
+    pub fn new_at_point(
+        bodies: [BodyHandle; 2],
+        anchor: Vector<f64>,
+        world: &World,
+    ) -> Option<Self> {
+        let (a, b) = world_bodies(world, bodies)?;
+
+        Some(Self::new(
+            bodies,
+            [a.world_to_local(anchor), b.world_to_local(anchor)],
+            b.angular.orientation - a.angular.orientation,
+        ))
+    }
+
+    pub fn with_softness(mut self, frequency: f64, damping_ratio: f64) -> Self {
+        self.softness = Some(Softness::new(frequency, damping_ratio));
+        self
+    }
+
+    pub fn impulse(&self) -> Vector<f64> {
+        self.impulse
+    }
+
+    pub fn angular_impulse(&self) -> f64 {
+        self.angular_impulse
+    }
+}
+
+joint_basics!(WeldJoint, angular);
+
+impl Constraint for WeldJoint {
+    fn bodies(&self) -> [BodyHandle; 2] {
+        self.bodies
+    }
+
+    fn prepare(
+        &mut self,
+        delta_time: f64,
+        settings: &ConstraintSettings,
+        bodies: &mut IdMap<Body>,
+    ) {
+        self.prepared = None;
+
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let Some(mass) = point_mass(a, b, &a_to_anchor, &b_to_anchor) else {
+            return;
+        };
+
+        let angular_mass = angular_mass(a, b);
+        let angle_error = b.angular.orientation - a.angular.orientation - self.reference_angle;
+
+        // Rigid welds are pulled back on positions instead
+        let (bias_rate, mass_scale, impulse_scale) = match self.softness {
+            Some(softness) if softness.is_soft() => {
+                let omega = 2.0 * f64::consts::PI * softness.frequency;
+                let a1 = 2.0 * softness.damping_ratio + delta_time * omega;
+                let a2 = delta_time * omega * a1;
+                let a3 = 1.0 / (1.0 + a2);
+
+                (omega / a1, a2 * a3, a3)
+            }
+            _ => (0.0, 1.0, 0.0),
+        };
+
+        if !settings.warm_starting {
+            self.impulse = Vector::zeros();
+            self.angular_impulse = 0.0;
+        }
+
+        if angular_mass == 0.0 {
+            self.angular_impulse = 0.0;
+        }
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            self.impulse,
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+        apply_angular_impulse(bodies, self.bodies, self.angular_impulse);
+
+        self.prepared = Some(Prepared {
+            a_to_anchor,
+            b_to_anchor,
+            mass,
+            angular_mass,
+            error: b_anchor - a_anchor,
+            angle_error,
+            bias_rate,
+            mass_scale,
+            impulse_scale,
+        });
+    }
+
+    fn solve(&mut self, bodies: &mut IdMap<Body>) {
+        let Some(prepared) = &self.prepared else {
+            return;
+        };
+
+        if prepared.angular_mass > 0.0 {
+            let velocity = relative_angular_velocity(bodies, self.bodies);
+            let lambda = -prepared.mass_scale
+                * prepared.angular_mass
+                * (velocity + prepared.bias_rate * prepared.angle_error)
+                - prepared.impulse_scale * self.angular_impulse;
+            self.angular_impulse += lambda;
+
+            apply_angular_impulse(bodies, self.bodies, lambda);
+        }
+
+        let velocity = relative_velocity(
+            bodies,
+            self.bodies,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+        let lambda = -prepared.mass_scale
+            * (prepared.mass * (velocity + prepared.bias_rate * prepared.error))
+            - prepared.impulse_scale * self.impulse;
+        self.impulse += lambda;
+
+        apply_impulse(
+            bodies,
+            self.bodies,
+            lambda,
+            &prepared.a_to_anchor,
+            &prepared.b_to_anchor,
+        );
+    }
+
+    fn solve_positions(&mut self, settings: &ConstraintSettings, bodies: &mut IdMap<Body>) {
+        // Soft welds spring back on velocities
+        if self.softness.is_some_and(|softness| softness.is_soft()) {
+            return;
+        }
+
+        let (Some(a), Some(b)) = (bodies.get(self.bodies[0]), bodies.get(self.bodies[1])) else {
+            return;
+        };
+
+        let angular_mass = angular_mass(a, b);
+        let angle_error = b.angular.orientation - a.angular.orientation - self.reference_angle;
+
+        if angular_mass > 0.0 {
+            apply_angular_position_impulse(
+                bodies,
+                self.bodies,
+                -settings.correction_level * angle_error * angular_mass,
+            );
+        }
+
+        // Straightening the angle moved the anchors
+        let Some(Anchors {
+            a,
+            b,
+            a_anchor,
+            b_anchor,
+            a_to_anchor,
+            b_to_anchor,
+        }) = anchors(bodies, self.bodies, &self.local_anchors)
+        else {
+            return;
+        };
+
+        let Some(mass) = point_mass(a, b, &a_to_anchor, &b_to_anchor) else {
+            return;
+        };
+
+        apply_position_impulse(
+            bodies,
+            self.bodies,
+            -settings.correction_level * (mass * (b_anchor - a_anchor)),
+            &a_to_anchor,
+            &b_to_anchor,
+        );
+    }
+
+    fn reaction_impulse(&self) -> f64 {
+        self.impulse.norm()
+    }
+
+    fn reaction_angular_impulse(&self) -> f64 {
+        self.angular_impulse.abs()
+    }
+
+    fn break_threshold(&self) -> Option<f64> {
+        self.break_threshold
+    }
+
+    fn angular_break_threshold(&self) -> Option<f64> {
+        self.angular_break_threshold
+    }
+}
@@ -1,119 +1,178 @@
 use std::{any::Any, collections::HashMap};
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    body::{AngularState, Body, LinearState},
+    body::{AngularState, Body, BodyHandle, LinearState},
     id_map::{Id, IdMap},
+    membership::{BodyGroup, BodyMembership, BodySet},
     types::{math::*, *},
 };
 
 // Maybe add init
-pub trait Effector: Any + AsAny {
+pub trait Effector: Any + AsAny + BodyMembership {
     fn apply(&self, bodies: &mut IdMap<Body>);
+
+    // Pairs of bodies tied together so they sleep and wake as one island
+    fn links(&self) -> Vec<[BodyHandle; 2]> {
+        Vec::new()
+    }
+
+    // Effectors with a changeable list of bodies return themselves
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        None
+    }
 }
 
+pub type BoxedEffector = Box<dyn Effector + Send + Sync>;
+pub type EffectorHandle = Id<BoxedEffector>;
+
+#[derive(Serialize, Deserialize)]
 pub struct ConstantForce {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
     pub force: Vector<f64>,
 }
 
 impl ConstantForce {
-    pub fn new(bodies: Vec<Id>, force: Vector<f64>) -> Self {
-        Self { bodies, force }
+    pub fn new(bodies: impl Into<BodySet>, force: Vector<f64>) -> Self {
+        Self {
+            bodies: bodies.into(),
+            force,
+        }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for ConstantForce {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for ConstantForce {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 impl Effector for ConstantForce {
     fn apply(&self, bodies: &mut IdMap<Body>) {
-        for id in &self.bodies {
-            if let Some(body) = bodies.get_mut(*id) {
-                body.linear.force += self.force;
-            }
-        }
+        self.bodies.for_each_awake(bodies, |body| {
+            body.linear.force += self.force;
+        });
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ConstantAcceleration {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
     pub acceleration: Vector<f64>,
 }
 
 impl ConstantAcceleration {
-    pub fn new(bodies: Vec<Id>, acceleration: Vector<f64>) -> Self {
+    pub fn new(bodies: impl Into<BodySet>, acceleration: Vector<f64>) -> Self {
         Self {
-            bodies,
+            bodies: bodies.into(),
             acceleration,
         }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for ConstantAcceleration {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for ConstantAcceleration {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 impl Effector for ConstantAcceleration {
     fn apply(&self, bodies: &mut IdMap<Body>) {
-        for id in &self.bodies {
-            if let Some(body) = bodies.get_mut(*id) {
-                body.linear.force += self.acceleration * body.linear.mass;
-            }
-        }
+        self.bodies.for_each_awake(bodies, |body| {
+            body.linear.force += self.acceleration * body.linear.mass;
+        });
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Gravity {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
     pub gravitational_constant: f64,
 }
 
 impl Gravity {
-    pub fn new(bodies: Vec<Id>, gravitational_constant: f64) -> Self {
+    pub fn new(bodies: impl Into<BodySet>, gravitational_constant: f64) -> Self {
         Self {
-            bodies,
+            bodies: bodies.into(),
             gravitational_constant,
         }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for Gravity {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for Gravity {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 impl Effector for Gravity {
     fn apply(&self, bodies: &mut IdMap<Body>) {
-        for i in 0..self.bodies.len() {
-            for j in (i + 1)..self.bodies.len() {
-                let (a_id, b_id) = (self.bodies[i], self.bodies[j]);
+        let ids = self.bodies.resolve(bodies);
+
+        for i in 0..ids.len() {
+            for j in (i + 1)..ids.len() {
+                let (a_id, b_id) = (ids[i], ids[j]);
                 let Some(a) = bodies.get(a_id) else { continue };
                 let Some(b) = bodies.get(b_id) else { continue };
 
+                if a.is_sleeping() && b.is_sleeping() {
+                    continue;
+                }
+
                 let direction = b.linear.position - a.linear.position;
                 // TODO: Review this
                 let distance_squared = direction.norm_squared().max(0.0001);
@@ -127,50 +186,69 @@ impl Effector for Gravity {
             }
         }
     }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
+    }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ConstantTorque {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
     pub torque: f64,
 }
 
 impl ConstantTorque {
-    pub fn new(bodies: Vec<Id>, torque: f64) -> Self {
-        Self { bodies, torque }
+    pub fn new(bodies: impl Into<BodySet>, torque: f64) -> Self {
+        Self {
+            bodies: bodies.into(),
+            torque,
+        }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for ConstantTorque {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for ConstantTorque {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 impl Effector for ConstantTorque {
     fn apply(&self, bodies: &mut IdMap<Body>) {
-        for id in &self.bodies {
-            if let Some(body) = bodies.get_mut(*id) {
-                body.angular.torque += self.torque;
-            }
-        }
+        self.bodies.for_each_awake(bodies, |body| {
+            body.angular.torque += self.torque;
+        });
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Spring {
-    pub bodies: [Id; 2],
+    pub bodies: [BodyHandle; 2],
     pub length: f64,
     pub elasticity: f64,
 }
 
 impl Spring {
-    pub fn new(bodies: [Id; 2], length: f64, elasticity: f64) -> Self {
+    pub fn new(bodies: [BodyHandle; 2], length: f64, elasticity: f64) -> Self {
         Self {
             bodies,
             length,
@@ -178,7 +256,11 @@ impl Spring {
         }
     }
 
-    pub fn new_auto_length(body_ids: [Id; 2], elasticity: f64, bodies: &mut IdMap<Body>) -> Self {
+    pub fn new_auto_length(
+        body_ids: [BodyHandle; 2],
+        elasticity: f64,
+        bodies: &mut IdMap<Body>,
+    ) -> Self {
         let (a, b) = (
             bodies.get(body_ids[0]).unwrap(),
             bodies.get(body_ids[1]).unwrap(),
@@ -193,7 +275,18 @@ impl Spring {
     }
 }
 
+impl BodyMembership for Spring {
+    // A spring needs both of its bodies
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        !self.bodies.contains(&body)
+    }
+}
+
 impl Effector for Spring {
+    fn links(&self) -> Vec<[BodyHandle; 2]> {
+        vec![self.bodies]
+    }
+
     fn apply(&self, bodies: &mut IdMap<Body>) {
         let (a_id, b_id) = (self.bodies[0], self.bodies[1]);
         let Some(a) = bodies.get(a_id) else {
@@ -204,6 +297,10 @@ impl Effector for Spring {
             return;
         };
 
+        if a.is_sleeping() && b.is_sleeping() {
+            return;
+        }
+
         let length = a.linear.position.metric_distance(&b.linear.position);
         let force = self.elasticity * (length - self.length);
         let direction = (b.linear.position - a.linear.position).normalize();
@@ -214,43 +311,55 @@ impl Effector for Spring {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Drag {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
     // Better name?
     pub coefficient: f64,
 }
 
 impl Drag {
-    pub fn new(bodies: Vec<Id>, coefficient: f64) -> Self {
+    pub fn new(bodies: impl Into<BodySet>, coefficient: f64) -> Self {
         Self {
-            bodies,
+            bodies: bodies.into(),
             coefficient,
         }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for Drag {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for Drag {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 // Doesn't account for area
 impl Effector for Drag {
     fn apply(&self, bodies: &mut IdMap<Body>) {
-        for id in &self.bodies {
-            if let Some(body) = bodies.get_mut(*id) {
-                body.linear.force += -(1.0 / 2.0)
-                    * body.linear.velocity.norm()
-                    * body.linear.velocity
-                    * self.coefficient;
-            }
-        }
+        self.bodies.for_each_awake(bodies, |body| {
+            body.linear.force += -(1.0 / 2.0)
+                * body.linear.velocity.norm()
+                * body.linear.velocity
+                * self.coefficient;
+        });
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
@@ -1,93 +1,260 @@
-use std::collections::HashSet;
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
 
-pub type Id = usize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+// The generation changes every time a slot is freed so old ids to it are rejected
+// T is the type the id points at so ids from different maps can't be mixed up
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Id<T> {
+    pub index: usize,
+    pub generation: u32,
+    #[serde(skip)]
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    fn new(index: usize, generation: u32) -> Self {
+        Self {
+            index,
+            generation,
+            marker: PhantomData,
+        }
+    }
+}
+
+// Implemented by hand so T doesn't need to implement them
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+// Ordered by index then generation
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.index, self.generation).cmp(&(other.index, other.generation))
+    }
+}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Id")
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Slot<T> {
+    value: Option<T>,
+    generation: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct IdMap<T> {
-    values: Vec<Option<T>>,
-    next_id: Id,
-    // Maybe use vecdequeue and if free id isnt free anymore just use next
-    free_ids: HashSet<Id>,
+    // Slots are never popped so their generation is kept
+    // Values are always iterated in slot order
+    slots: Vec<Slot<T>>,
+    // Ordered so the lowest free slot is always reused first
+    free_ids: BTreeSet<usize>,
 }
 
 impl<T> IdMap<T> {
     pub fn new() -> Self {
         Self {
-            values: Vec::new(),
-            next_id: 0,
-            free_ids: HashSet::new(),
+            slots: Vec::new(),
+            free_ids: BTreeSet::new(),
         }
     }
 
-    pub fn add(&mut self, value: T) -> Id {
-        if let Some(&id) = self.free_ids.iter().next() {
-            // Use a free space
-            self.values[id] = Some(value);
-            self.free_ids.take(&id).unwrap()
+    pub fn add(&mut self, value: T) -> Id<T> {
+        if let Some(index) = self.free_ids.pop_first() {
+            // Use the lowest free space
+            let slot = &mut self.slots[index];
+            slot.value = Some(value);
+
+            Id::new(index, slot.generation)
         } else {
             // Grow size
-            self.next_id += 1;
-            self.values.push(Some(value));
-            self.next_id - 1
+            self.slots.push(Slot {
+                value: Some(value),
+                generation: 0,
+            });
+
+            Id::new(self.slots.len() - 1, 0)
         }
     }
 
-    pub fn remove(&mut self, id: Id) {
-        if id >= self.next_id || self.values.is_empty() {
+    pub fn remove(&mut self, id: Id<T>) {
+        if !self.contains(id) {
             return;
         }
 
-        if id == self.next_id - 1 {
-            self.values[id] = None;
-
-            while let Some(None) = self.values.last() {
-                self.values.pop();
-                self.free_ids.remove(&(self.next_id - 1));
-                self.next_id -= 1;
+        let slot = &mut self.slots[id.index];
+        slot.value = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_ids.insert(id.index);
+    }
 
-                if self.next_id == 0 {
-                    break;
-                }
+    // Stale ids stay invalid after a clear
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
             }
-        } else {
-            if self.values[id].is_some() {
-                self.values[id] = None;
-                self.free_ids.insert(id);
+
+            self.free_ids.insert(index);
+        }
+    }
+
+    // Removes every value the closure returns false for
+    pub fn retain(&mut self, mut f: impl FnMut(Id<T>, &mut T) -> bool) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let Some(value) = slot.value.as_mut() else {
+                continue;
+            };
+
+            if !f(Id::new(index, slot.generation), value) {
+                slot.value = None;
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free_ids.insert(index);
             }
         }
     }
 
-    pub fn clear(&mut self) {
-        self.values = Vec::new();
-        self.next_id = 0;
-        self.free_ids = HashSet::new();
+    pub fn contains(&self, id: Id<T>) -> bool {
+        self.get(id).is_some()
     }
 
-    pub fn get(&self, id: Id) -> Option<&T> {
-        if let Some(value) = self.values.get(id) {
-            value.as_ref()
-        } else {
-            None
+    pub fn get(&self, id: Id<T>) -> Option<&T> {
+        match self.slots.get(id.index) {
+            Some(slot) if slot.generation == id.generation => slot.value.as_ref(),
+            _ => None,
         }
     }
 
-    pub fn get_mut(&mut self, id: Id) -> Option<&mut T> {
-        if let Some(value) = self.values.get_mut(id) {
-            value.as_mut()
-        } else {
-            None
+    pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {
+        match self.slots.get_mut(id.index) {
+            Some(slot) if slot.generation == id.generation => slot.value.as_mut(),
+            _ => None,
         }
     }
 
-    pub fn get_disjoint_mut<const N: usize>(&mut self, ids: [Id; N]) -> [&mut Option<T>; N] {
-        self.values.get_disjoint_mut(ids).unwrap()
+    // Gives None for every id if any are repeated
+    pub fn get_disjoint_mut<const N: usize>(&mut self, ids: [Id<T>; N]) -> [Option<&mut T>; N] {
+        let Ok(slots) = self.slots.get_disjoint_mut(ids.map(|id| id.index)) else {
+            return std::array::from_fn(|_| None);
+        };
+
+        let mut slots = slots.into_iter();
+
+        std::array::from_fn(|i| {
+            let slot = slots.next().unwrap();
+
+            if slot.generation == ids[i].generation {
+                slot.value.as_mut()
+            } else {
+                None
+            }
+        })
+    }
+
+    // Converts every value while keeping the same ids and free slots
+    pub fn try_map<U, E>(&self, mut f: impl FnMut(&T) -> Result<U, E>) -> Result<IdMap<U>, E> {
+        let mut slots = Vec::with_capacity(self.slots.len());
+
+        for slot in &self.slots {
+            slots.push(Slot {
+                value: slot.value.as_ref().map(&mut f).transpose()?,
+                generation: slot.generation,
+            });
+        }
+
+        Ok(IdMap {
+            slots,
+            free_ids: self.free_ids.clone(),
+        })
     }
 
     pub fn values(&self) -> impl Iterator<Item = &T> {
-        self.values.iter().filter_map(|v| v.as_ref())
+        self.slots.iter().filter_map(|s| s.value.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, s)| s.value.as_ref().map(|v| (Id::new(index, s.generation), v)))
     }
 
     pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
-        self.values.iter_mut().filter_map(|v| v.as_mut())
+        self.slots.iter_mut().filter_map(|s| s.value.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_ids_are_rejected_after_reuse() {
+        let mut map = IdMap::new();
+        let old = map.add("old");
+        map.remove(old);
+
+        // Only one slot is free so the new value has to reuse it
+        let new = map.add("new");
+        assert_eq!(new.index, old.index);
+        assert_ne!(new.generation, old.generation);
+
+        assert!(map.get(old).is_none());
+        assert!(map.get_mut(old).is_none());
+        assert!(!map.contains(old));
+
+        // Removing with the old id leaves the new value alone
+        map.remove(old);
+        assert_eq!(map.get(new), Some(&"new"));
+    }
+
+    #[test]
+    fn stale_ids_are_rejected_after_clear() {
+        let mut map = IdMap::new();
+        let id = map.add(1);
+        map.clear();
+        let reused = map.add(2);
+
+        assert!(map.get(id).is_none());
+        assert_eq!(map.get(reused), Some(&2));
     }
 }
@@ -1,104 +1,157 @@
 use std::any::Any;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    body::Body,
+    body::{Body, BodyHandle, BodyType},
     id_map::{Id, IdMap},
+    membership::{BodyGroup, BodyMembership, BodySet},
     types::{math::*, *},
 };
 
 // Maybe add init
-pub trait Integrator {
+pub trait Integrator: Any + AsAny + BodyMembership {
     fn step(&mut self, delta_time: f64, bodies: &mut IdMap<Body>);
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        None
+    }
 }
 
+pub type BoxedIntegrator = Box<dyn Integrator + Send + Sync>;
+pub type IntegratorHandle = Id<BoxedIntegrator>;
+
+#[derive(Serialize, Deserialize)]
 pub struct ExplicitEuler {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
 }
 
 impl ExplicitEuler {
-    pub fn new(bodies: Vec<Id>) -> Self {
-        Self { bodies }
+    pub fn new(bodies: impl Into<BodySet>) -> Self {
+        Self {
+            bodies: bodies.into(),
+        }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for ExplicitEuler {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for ExplicitEuler {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 impl Integrator for ExplicitEuler {
     fn step(&mut self, delta_time: f64, bodies: &mut IdMap<Body>) {
-        for id in &self.bodies {
-            if let Some(body) = bodies.get_mut(*id) {
-                let linear = &mut body.linear;
+        self.bodies.for_each_awake(bodies, |body| {
+            if body.body_type == BodyType::Static {
+                return;
+            }
+
+            // Forces only move dynamic bodies
+            let dynamic = body.body_type == BodyType::Dynamic;
+            let (inverse_mass, inverse_inertia) = (body.inverse_mass(), body.inverse_inertia());
 
-                linear.position += linear.velocity * delta_time;
-                linear.velocity += (linear.force / linear.mass) * delta_time;
-                // Should this be in the integrator?
-                linear.force = Vector::zeros();
+            let linear = &mut body.linear;
 
-                let angular = &mut body.angular;
+            linear.position += linear.velocity * delta_time;
+            if dynamic {
+                linear.velocity += linear.force * inverse_mass * delta_time;
+            }
+            // Should this be in the integrator?
+            linear.force = Vector::zeros();
 
-                // Is this dodgy
-                if angular.inertia == 0.0 {
-                    continue;
-                };
+            let angular = &mut body.angular;
 
-                angular.orientation += angular.velocity * delta_time;
-                angular.velocity += (angular.torque / angular.inertia) * delta_time;
-                angular.torque = 0.0;
+            angular.orientation += angular.velocity * delta_time;
+            if dynamic {
+                angular.velocity += angular.torque * inverse_inertia * delta_time;
             }
-        }
+            angular.torque = 0.0;
+        });
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct SemiImplicitEuler {
-    pub bodies: Vec<Id>,
+    pub bodies: BodySet,
 }
 
 impl SemiImplicitEuler {
-    pub fn new(bodies: Vec<Id>) -> Self {
-        Self { bodies }
+    pub fn new(bodies: impl Into<BodySet>) -> Self {
+        Self {
+            bodies: bodies.into(),
+        }
     }
+}
 
-    fn add_body(&mut self, id: Id) {
-        if !self.bodies.contains(&id) {
-            self.bodies.push(id);
-        }
+impl BodyGroup for SemiImplicitEuler {
+    fn add_body(&mut self, body: BodyHandle) {
+        self.bodies.add_body(body);
     }
 
-    fn remove_body(&mut self, id: Id) {
-        if let Some(index) = self.bodies.iter().position(|i| *i == id) {
-            self.bodies.remove(index);
-        }
+    fn remove_body(&mut self, body: BodyHandle) {
+        self.bodies.remove_body(body);
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains_body(id, body)
+    }
+}
+
+impl BodyMembership for SemiImplicitEuler {
+    fn body_removed(&mut self, body: BodyHandle) -> bool {
+        self.remove_body(body);
+        true
     }
 }
 
 impl Integrator for SemiImplicitEuler {
     fn step(&mut self, delta_time: f64, bodies: &mut IdMap<Body>) {
-        for id in &self.bodies {
-            if let Some(body) = bodies.get_mut(*id) {
-                let linear = &mut body.linear;
+        self.bodies.for_each_awake(bodies, |body| {
+            if body.body_type == BodyType::Static {
+                return;
+            }
+
+            // Forces only move dynamic bodies
+            let dynamic = body.body_type == BodyType::Dynamic;
+            let (inverse_mass, inverse_inertia) = (body.inverse_mass(), body.inverse_inertia());
 
-                linear.velocity += (linear.force / linear.mass) * delta_time;
-                linear.position += linear.velocity * delta_time;
+            let linear = &mut body.linear;
 
-                let angular = &mut body.angular;
+            if dynamic {
+                linear.velocity += linear.force * inverse_mass * delta_time;
+            }
+            linear.position += linear.velocity * delta_time;
 
-                if angular.inertia == 0.0 {
-                    continue;
-                };
+            let angular = &mut body.angular;
 
-                angular.velocity += (angular.torque / angular.inertia) * delta_time;
-                angular.orientation += angular.velocity * delta_time;
+            if dynamic {
+                angular.velocity += angular.torque * inverse_inertia * delta_time;
             }
-        }
+            angular.orientation += angular.velocity * delta_time;
+        });
+    }
+
+    fn as_body_group_mut(&mut self) -> Option<&mut dyn BodyGroup> {
+        Some(self)
     }
 }
@@ -1,9 +1,16 @@
 pub mod body;
 pub mod collision;
 pub mod components;
+pub mod constraint;
 pub mod effector;
 pub mod id_map;
 pub mod integrator;
+pub mod membership;
+pub mod replay;
+pub mod sleep;
+pub mod snapshot;
 pub mod soft_body;
+pub mod stage;
+pub mod timestep;
 pub mod types;
 pub mod world;
@@ -0,0 +1,125 @@
+use std::{borrow::Cow, collections::HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{Body, BodyHandle, BodyType},
+    id_map::IdMap,
+};
+
+// Lets the world tell anything holding body handles that a body is gone
+pub trait BodyMembership {
+    // Returns false if it can't work without the body and should be removed from the world
+    fn body_removed(&mut self, body: BodyHandle) -> bool;
+}
+
+// Something that acts on a list of bodies which can change at runtime
+pub trait BodyGroup {
+    fn add_body(&mut self, body: BodyHandle);
+    fn remove_body(&mut self, body: BodyHandle);
+    // Takes the body too so it can be checked against a query
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyQuery {
+    All,
+    // Bodies that aren't static or kinematic
+    Dynamic,
+    // Bodies with at least one of the tags
+    AnyTags(u32),
+    // Bodies with every one of the tags
+    AllTags(u32),
+}
+
+impl BodyQuery {
+    pub fn matches(&self, body: &Body) -> bool {
+        match self {
+            BodyQuery::All => true,
+            BodyQuery::Dynamic => body.body_type == BodyType::Dynamic,
+            BodyQuery::AnyTags(tags) => body.tags & tags != 0,
+            BodyQuery::AllTags(tags) => body.tags & tags == *tags,
+        }
+    }
+}
+
+// Bodies listed by hand plus any matching the query, checked every time it's resolved
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BodySet {
+    pub bodies: Vec<BodyHandle>,
+    pub query: Option<BodyQuery>,
+}
+
+impl BodySet {
+    pub fn new(bodies: Vec<BodyHandle>) -> Self {
+        Self {
+            bodies,
+            query: None,
+        }
+    }
+
+    pub fn from_query(query: BodyQuery) -> Self {
+        Self {
+            bodies: Vec::new(),
+            query: Some(query),
+        }
+    }
+
+    // Listed bodies first then matching bodies in storage order, only a query needs a new list
+    pub fn resolve(&self, bodies: &IdMap<Body>) -> Cow<'_, [BodyHandle]> {
+        let Some(query) = self.query else {
+            return Cow::Borrowed(&self.bodies);
+        };
+
+        let mut resolved = self.bodies.clone();
+        let listed: HashSet<BodyHandle> = self.bodies.iter().copied().collect();
+
+        for (id, body) in bodies.iter() {
+            if query.matches(body) && !listed.contains(&id) {
+                resolved.push(id);
+            }
+        }
+
+        Cow::Owned(resolved)
+    }
+
+    // Runs f on every resolved body that isn't sleeping
+    pub fn for_each_awake(&self, bodies: &mut IdMap<Body>, mut f: impl FnMut(&mut Body)) {
+        for &id in self.resolve(bodies).iter() {
+            if let Some(body) = bodies.get_mut(id).filter(|body| !body.is_sleeping()) {
+                f(body);
+            }
+        }
+    }
+}
+
+impl From<Vec<BodyHandle>> for BodySet {
+    fn from(bodies: Vec<BodyHandle>) -> Self {
+        BodySet::new(bodies)
+    }
+}
+
+impl From<BodyQuery> for BodySet {
+    fn from(query: BodyQuery) -> Self {
+        BodySet::from_query(query)
+    }
+}
+
+// Only changes the listed bodies, bodies matching the query are always included
+impl BodyGroup for BodySet {
+    fn add_body(&mut self, body: BodyHandle) {
+        if !self.bodies.contains(&body) {
+            self.bodies.push(body);
+        }
+    }
+
+    fn remove_body(&mut self, body: BodyHandle) {
+        if let Some(index) = self.bodies.iter().position(|b| *b == body) {
+            self.bodies.remove(index);
+        }
+    }
+
+    fn contains_body(&self, id: BodyHandle, body: &Body) -> bool {
+        self.bodies.contains(&id) || self.query.is_some_and(|query| query.matches(body))
+    }
+}
@@ -0,0 +1,638 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{Body, BodyHandle},
+    collision::PipelineHandle,
+    constraint::{BoxedConstraint, ConstraintHandle},
+    effector::{BoxedEffector, EffectorHandle},
+    integrator::IntegratorHandle,
+    snapshot::{SerializedObject, SnapshotError, WorldSnapshot},
+    types::math::*,
+    world::World,
+};
+
+// Everything done to a world after the initial snapshot, in order
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ReplayEvent {
+    AddBody(Body),
+    RemoveBody(BodyHandle),
+    SetPosition(BodyHandle, Vector<f64>),
+    SetLinearVelocity(BodyHandle, Vector<f64>),
+    SetOrientation(BodyHandle, f64),
+    SetAngularVelocity(BodyHandle, f64),
+    ApplyForce(BodyHandle, Vector<f64>),
+    // Force then world point
+    ApplyForceAtPoint(BodyHandle, Vector<f64>, Vector<f64>),
+    ApplyTorque(BodyHandle, f64),
+    ApplyImpulse(BodyHandle, Vector<f64>),
+    // Impulse then world point
+    ApplyImpulseAtPoint(BodyHandle, Vector<f64>, Vector<f64>),
+    ApplyTorqueImpulse(BodyHandle, f64),
+    AddEffector(SerializedObject),
+    RemoveEffector(EffectorHandle),
+    // The whole effector after it was changed
+    SetEffector(EffectorHandle, SerializedObject),
+    AddBodyToEffector(EffectorHandle, BodyHandle),
+    RemoveBodyFromEffector(EffectorHandle, BodyHandle),
+    AddBodyToIntegrator(IntegratorHandle, BodyHandle),
+    RemoveBodyFromIntegrator(IntegratorHandle, BodyHandle),
+    AddBodyToCollisionPipeline(PipelineHandle, BodyHandle),
+    RemoveBodyFromCollisionPipeline(PipelineHandle, BodyHandle),
+    AddConstraint(SerializedObject),
+    RemoveConstraint(ConstraintHandle),
+    // The whole constraint after it was changed
+    SetConstraint(ConstraintHandle, SerializedObject),
+    // Checksum of the bodies after the step
+    Update { delta_time: f64, checksum: u64 },
+    StepFixed { elapsed_time: f64, checksum: u64 },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayLog {
+    pub initial: WorldSnapshot,
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Snapshot(SnapshotError),
+    // The body state after a step didn't match the recording
+    Diverged {
+        frame: usize,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Snapshot(error) => write!(f, "{}", error),
+            ReplayError::Diverged {
+                frame,
+                expected,
+                found,
+            } => write!(
+                f,
+                "replay diverged at frame {}: expected checksum {:x}, found {:x}",
+                frame, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<SnapshotError> for ReplayError {
+    fn from(error: SnapshotError) -> Self {
+        ReplayError::Snapshot(error)
+    }
+}
+
+// FNV-1a over the exact bits of every body's state so any difference is caught
+pub fn checksum(world: &World) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    let mut write = |value: u64| {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    for (id, body) in world.iter_bodies() {
+        write(id.index as u64);
+        write(id.generation as u64);
+        write(body.linear.position.x.to_bits());
+        write(body.linear.position.y.to_bits());
+        write(body.linear.velocity.x.to_bits());
+        write(body.linear.velocity.y.to_bits());
+        write(body.angular.orientation.to_bits());
+        write(body.angular.velocity.to_bits());
+    }
+
+    hash
+}
+
+// Wraps a world and records every change made through it
+pub struct Recorder {
+    world: World,
+    log: ReplayLog,
+}
+
+impl Recorder {
+    pub fn new(world: World) -> Result<Self, SnapshotError> {
+        let initial = world.snapshot()?;
+
+        Ok(Self {
+            world,
+            log: ReplayLog {
+                initial,
+                events: Vec::new(),
+            },
+        })
+    }
+
+    // Changes have to go through the recorder so only read access is given
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn log(&self) -> &ReplayLog {
+        &self.log
+    }
+
+    pub fn finish(self) -> (World, ReplayLog) {
+        (self.world, self.log)
+    }
+
+    pub fn add_body(&mut self, body: Body) -> BodyHandle {
+        self.log.events.push(ReplayEvent::AddBody(body.clone()));
+        self.world.add_body(body)
+    }
+
+    pub fn remove_body(&mut self, id: BodyHandle) {
+        self.record(ReplayEvent::RemoveBody(id));
+    }
+
+    pub fn set_position(&mut self, id: BodyHandle, position: Vector<f64>) {
+        self.record(ReplayEvent::SetPosition(id, position));
+    }
+
+    pub fn set_linear_velocity(&mut self, id: BodyHandle, velocity: Vector<f64>) {
+        self.record(ReplayEvent::SetLinearVelocity(id, velocity));
+    }
+
+    pub fn set_orientation(&mut self, id: BodyHandle, orientation: f64) {
+        self.record(ReplayEvent::SetOrientation(id, orientation));
+    }
+
+    pub fn set_angular_velocity(&mut self, id: BodyHandle, velocity: f64) {
+        self.record(ReplayEvent::SetAngularVelocity(id, velocity));
+    }
+
+    pub fn apply_force(&mut self, id: BodyHandle, force: Vector<f64>) {
+        self.record(ReplayEvent::ApplyForce(id, force));
+    }
+
+    pub fn apply_force_at_point(&mut self, id: BodyHandle, force: Vector<f64>, point: Vector<f64>) {
+        self.record(ReplayEvent::ApplyForceAtPoint(id, force, point));
+    }
+
+    pub fn apply_torque(&mut self, id: BodyHandle, torque: f64) {
+        self.record(ReplayEvent::ApplyTorque(id, torque));
+    }
+
+    pub fn apply_impulse(&mut self, id: BodyHandle, impulse: Vector<f64>) {
+        self.record(ReplayEvent::ApplyImpulse(id, impulse));
+    }
+
+    pub fn apply_impulse_at_point(
+        &mut self,
+        id: BodyHandle,
+        impulse: Vector<f64>,
+        point: Vector<f64>,
+    ) {
+        self.record(ReplayEvent::ApplyImpulseAtPoint(id, impulse, point));
+    }
+
+    pub fn apply_torque_impulse(&mut self, id: BodyHandle, impulse: f64) {
+        self.record(ReplayEvent::ApplyTorqueImpulse(id, impulse));
+    }
+
+    // The effector has to be registered so it can be recorded
+    pub fn add_effector(
+        &mut self,
+        effector: BoxedEffector,
+    ) -> Result<EffectorHandle, SnapshotError> {
+        let object = self
+            .world
+            .get_snapshot_registry()
+            .serialize_effector(&effector)?;
+
+        self.log.events.push(ReplayEvent::AddEffector(object));
+        Ok(self.world.add_effector(effector))
+    }
+
+    pub fn remove_effector(&mut self, id: EffectorHandle) {
+        self.record(ReplayEvent::RemoveEffector(id));
+    }
+
+    // Returns false if the effector doesn't exist. A copy is changed and only swapped in once
+    // it's been recorded, so a failed recording leaves the world as it was
+    pub fn modify_effector(
+        &mut self,
+        id: EffectorHandle,
+        modify: impl FnOnce(&mut BoxedEffector),
+    ) -> Result<bool, SnapshotError> {
+        let Some(effector) = self.world.get_effector(id) else {
+            return Ok(false);
+        };
+
+        let registry = self.world.get_snapshot_registry();
+        let mut copy = registry.deserialize_effector(&registry.serialize_effector(effector)?)?;
+        modify(&mut copy);
+
+        let object = registry.serialize_effector(&copy)?;
+        // Set the same way a replay sets it
+        let modified = registry.deserialize_effector(&object)?;
+
+        *self.world.get_effector_mut(id).unwrap() = modified;
+        self.log.events.push(ReplayEvent::SetEffector(id, object));
+        Ok(true)
+    }
+
+    pub fn add_body_to_effector(&mut self, effector: EffectorHandle, body: BodyHandle) {
+        self.record(ReplayEvent::AddBodyToEffector(effector, body));
+    }
+
+    pub fn remove_body_from_effector(&mut self, effector: EffectorHandle, body: BodyHandle) {
+        self.record(ReplayEvent::RemoveBodyFromEffector(effector, body));
+    }
+
+    // The constraint has to be registered so it can be recorded
+    pub fn add_constraint(
+        &mut self,
+        constraint: BoxedConstraint,
+    ) -> Result<ConstraintHandle, SnapshotError> {
+        let object = self
+            .world
+            .get_snapshot_registry()
+            .serialize_constraint(&constraint)?;
+
+        self.log.events.push(ReplayEvent::AddConstraint(object));
+        Ok(self.world.add_constraint(constraint))
+    }
+
+    pub fn remove_constraint(&mut self, id: ConstraintHandle) {
+        self.record(ReplayEvent::RemoveConstraint(id));
+    }
+
+    // Returns false if the constraint doesn't exist, changes a copy the same way as
+    // modify_effector
+    pub fn modify_constraint(
+        &mut self,
+        id: ConstraintHandle,
+        modify: impl FnOnce(&mut BoxedConstraint),
+    ) -> Result<bool, SnapshotError> {
+        let Some(constraint) = self.world.get_constraint(id) else {
+            return Ok(false);
+        };
+
+        let registry = self.world.get_snapshot_registry();
+        let mut copy =
+            registry.deserialize_constraint(&registry.serialize_constraint(constraint)?)?;
+        modify(&mut copy);
+
+        let object = registry.serialize_constraint(&copy)?;
+        let modified = registry.deserialize_constraint(&object)?;
+
+        *self.world.get_constraint_mut(id).unwrap() = modified;
+        self.log.events.push(ReplayEvent::SetConstraint(id, object));
+        Ok(true)
+    }
+
+    pub fn add_body_to_integrator(&mut self, integrator: IntegratorHandle, body: BodyHandle) {
+        self.record(ReplayEvent::AddBodyToIntegrator(integrator, body));
+    }
+
+    pub fn remove_body_from_integrator(&mut self, integrator: IntegratorHandle, body: BodyHandle) {
+        self.record(ReplayEvent::RemoveBodyFromIntegrator(integrator, body));
+    }
+
+    pub fn add_body_to_collision_pipeline(
+        &mut self,
+        collision_pipeline: PipelineHandle,
+        body: BodyHandle,
+    ) {
+        self.record(ReplayEvent::AddBodyToCollisionPipeline(
+            collision_pipeline,
+            body,
+        ));
+    }
+
+    pub fn remove_body_from_collision_pipeline(
+        &mut self,
+        collision_pipeline: PipelineHandle,
+        body: BodyHandle,
+    ) {
+        self.record(ReplayEvent::RemoveBodyFromCollisionPipeline(
+            collision_pipeline,
+            body,
+        ));
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        self.world.update(delta_time);
+
+        let checksum = checksum(&self.world);
+        self.log.events.push(ReplayEvent::Update {
+            delta_time,
+            checksum,
+        });
+    }
+
+    pub fn step_fixed(&mut self, elapsed_time: f64) -> f64 {
+        let alpha = self.world.step_fixed(elapsed_time);
+
+        let checksum = checksum(&self.world);
+        self.log.events.push(ReplayEvent::StepFixed {
+            elapsed_time,
+            checksum,
+        });
+
+        alpha
+    }
+
+    fn record(&mut self, event: ReplayEvent) {
+        // Events without serialized data can't fail
+        apply_event(&mut self.world, &event).unwrap();
+        self.log.events.push(event);
+    }
+}
+
+// Returns the checksum from the recording if the event was a step
+fn apply_event(world: &mut World, event: &ReplayEvent) -> Result<Option<u64>, SnapshotError> {
+    match event {
+        ReplayEvent::AddBody(body) => {
+            world.add_body(body.clone());
+        }
+        ReplayEvent::RemoveBody(id) => world.remove_body(*id),
+        ReplayEvent::SetPosition(id, position) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.linear.position = *position;
+                body.wake_up();
+            }
+        }
+        ReplayEvent::SetLinearVelocity(id, velocity) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.linear.velocity = *velocity;
+                body.wake_up();
+            }
+        }
+        ReplayEvent::SetOrientation(id, orientation) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.angular.orientation = *orientation;
+                body.wake_up();
+            }
+        }
+        ReplayEvent::SetAngularVelocity(id, velocity) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.angular.velocity = *velocity;
+                body.wake_up();
+            }
+        }
+        ReplayEvent::ApplyForce(id, force) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.apply_force(*force);
+            }
+        }
+        ReplayEvent::ApplyForceAtPoint(id, force, point) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.apply_force_at_point(*force, *point);
+            }
+        }
+        ReplayEvent::ApplyTorque(id, torque) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.apply_torque(*torque);
+            }
+        }
+        ReplayEvent::ApplyImpulse(id, impulse) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.apply_impulse(*impulse);
+            }
+        }
+        ReplayEvent::ApplyImpulseAtPoint(id, impulse, point) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.apply_impulse_at_point(*impulse, *point);
+            }
+        }
+        ReplayEvent::ApplyTorqueImpulse(id, impulse) => {
+            if let Some(body) = world.get_body_mut(*id) {
+                body.apply_torque_impulse(*impulse);
+            }
+        }
+        ReplayEvent::AddEffector(object) => {
+            let effector = world.get_snapshot_registry().deserialize_effector(object)?;
+            world.add_effector(effector);
+        }
+        ReplayEvent::RemoveEffector(id) => world.remove_effector(*id),
+        ReplayEvent::SetEffector(id, object) => {
+            let effector = world.get_snapshot_registry().deserialize_effector(object)?;
+
+            if let Some(old) = world.get_effector_mut(*id) {
+                *old = effector;
+            }
+        }
+        ReplayEvent::AddBodyToEffector(effector, body) => {
+            world.add_body_to_effector(*effector, *body);
+        }
+        ReplayEvent::RemoveBodyFromEffector(effector, body) => {
+            world.remove_body_from_effector(*effector, *body);
+        }
+        ReplayEvent::AddBodyToIntegrator(integrator, body) => {
+            world.add_body_to_integrator(*integrator, *body);
+        }
+        ReplayEvent::RemoveBodyFromIntegrator(integrator, body) => {
+            world.remove_body_from_integrator(*integrator, *body);
+        }
+        ReplayEvent::AddBodyToCollisionPipeline(collision_pipeline, body) => {
+            world.add_body_to_collision_pipeline(*collision_pipeline, *body);
+        }
+        ReplayEvent::RemoveBodyFromCollisionPipeline(collision_pipeline, body) => {
+            world.remove_body_from_collision_pipeline(*collision_pipeline, *body);
+        }
+        ReplayEvent::AddConstraint(object) => {
+            let constraint = world
+                .get_snapshot_registry()
+                .deserialize_constraint(object)?;
+            world.add_constraint(constraint);
+        }
+        ReplayEvent::RemoveConstraint(id) => world.remove_constraint(*id),
+        ReplayEvent::SetConstraint(id, object) => {
+            let constraint = world
+                .get_snapshot_registry()
+                .deserialize_constraint(object)?;
+
+            if let Some(old) = world.get_constraint_mut(*id) {
+                *old = constraint;
+            }
+        }
+        ReplayEvent::Update {
+            delta_time,
+            checksum,
+        } => {
+            world.update(*delta_time);
+            return Ok(Some(*checksum));
+        }
+        ReplayEvent::StepFixed {
+            elapsed_time,
+            checksum,
+        } => {
+            world.step_fixed(*elapsed_time);
+            return Ok(Some(*checksum));
+        }
+    }
+
+    Ok(None)
+}
+
+// Plays a log back and checks every step matches the recording
+pub struct Replay<'a> {
+    world: World,
+    log: &'a ReplayLog,
+    next_event: usize,
+    frame: usize,
+}
+
+impl<'a> Replay<'a> {
+    // The world needs the same user types registered as the recorded one
+    pub fn new(mut world: World, log: &'a ReplayLog) -> Result<Self, ReplayError> {
+        world.restore(&log.initial)?;
+
+        Ok(Self {
+            world,
+            log,
+            next_event: 0,
+            frame: 0,
+        })
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn into_world(self) -> World {
+        self.world
+    }
+
+    // Number of steps replayed so far
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_event >= self.log.events.len()
+    }
+
+    // Applies events up to and including the next step
+    pub fn step(&mut self) -> Result<(), ReplayError> {
+        while let Some(event) = self.log.events.get(self.next_event) {
+            self.next_event += 1;
+
+            if let Some(expected) = apply_event(&mut self.world, event)? {
+                let found = checksum(&self.world);
+
+                if found != expected {
+                    return Err(ReplayError::Diverged {
+                        frame: self.frame,
+                        expected,
+                        found,
+                    });
+                }
+
+                self.frame += 1;
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> Result<(), ReplayError> {
+        while !self.is_finished() {
+            self.step()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{AngularState, LinearState, Shape},
+        collision::default::DefaultCollisionPipeline,
+        effector::ConstantAcceleration,
+        integrator::SemiImplicitEuler,
+    };
+
+    // Drops a box onto a ground, knocks it sideways and turns gravity up halfway through
+    fn record() -> ReplayLog {
+        let mut world = World::new();
+        let ground = world.add_body(Body::new_rigid(
+            LinearState::new(Vector::new(0.0, -50.0), Vector::zeros(), f64::INFINITY),
+            0.2,
+            AngularState::new(0.0, 0.0, f64::INFINITY),
+            Shape::new_rectangle(Vector::new(400.0, 20.0)),
+        ));
+        let falling = world.add_body(Body::new_rigid(
+            LinearState::new(Vector::zeros(), Vector::zeros(), 1.0),
+            0.2,
+            AngularState::new(0.3, 0.0, 1000.0),
+            Shape::new_rectangle(Vector::new(20.0, 20.0)),
+        ));
+
+        world.add_integrator(Box::new(SemiImplicitEuler::new(vec![falling])));
+        let gravity = world.add_effector(Box::new(ConstantAcceleration::new(
+            vec![falling],
+            Vector::new(0.0, -200.0),
+        )));
+        world.add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(vec![
+            ground, falling,
+        ])));
+
+        let mut recorder = Recorder::new(world).unwrap();
+
+        for frame in 0..60 {
+            if frame == 20 {
+                recorder.set_linear_velocity(falling, Vector::new(50.0, 0.0));
+            }
+
+            if frame == 30 {
+                recorder
+                    .modify_effector(gravity, |effector| {
+                        *effector = Box::new(ConstantAcceleration::new(
+                            vec![falling],
+                            Vector::new(0.0, -400.0),
+                        ));
+                    })
+                    .unwrap();
+            }
+
+            recorder.update(1.0 / 60.0);
+        }
+
+        recorder.finish().1
+    }
+
+    #[test]
+    fn replay_matches_the_recording() {
+        let log = record();
+        let mut replay = Replay::new(World::new(), &log).unwrap();
+
+        replay.run().unwrap();
+        assert_eq!(replay.frame(), 60);
+    }
+
+    #[test]
+    fn changed_log_diverges_at_the_changed_frame() {
+        let mut log = record();
+
+        for event in &mut log.events {
+            if let ReplayEvent::SetLinearVelocity(_, velocity) = event {
+                velocity.x += 1e-9;
+            }
+        }
+
+        let mut replay = Replay::new(World::new(), &log).unwrap();
+
+        match replay.run() {
+            Err(ReplayError::Diverged { frame, .. }) => assert_eq!(frame, 20),
+            _ => panic!("replay should have diverged"),
+        }
+    }
+}
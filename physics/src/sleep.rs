@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{Body, BodyHandle, BodyType},
+    id_map::IdMap,
+    timestep::Pose,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SleepSettings {
+    pub enabled: bool,
+    pub linear_threshold: f64,
+    pub angular_threshold: f64,
+    // Seconds a whole island has to stay under the thresholds before sleeping
+    pub time_to_sleep: f64,
+}
+
+impl SleepSettings {
+    pub fn new(linear_threshold: f64, angular_threshold: f64, time_to_sleep: f64) -> Self {
+        Self {
+            enabled: true,
+            linear_threshold,
+            angular_threshold,
+            time_to_sleep,
+        }
+    }
+
+    fn is_moving(&self, body: &Body) -> bool {
+        body.linear.velocity.norm() > self.linear_threshold
+            || body.angular.velocity.abs() > self.angular_threshold
+    }
+
+    // Uses how far the body moved since the last check as bodies in a stack can keep some
+    // velocity from the resolver without actually going anywhere
+    fn has_moved(&self, body: &Body, delta_time: f64) -> bool {
+        let linear_speed = body
+            .linear
+            .position
+            .metric_distance(&body.rest_pose.position)
+            / delta_time;
+        let angular_speed =
+            (body.angular.orientation - body.rest_pose.orientation).abs() / delta_time;
+
+        linear_speed > self.linear_threshold || angular_speed > self.angular_threshold
+    }
+
+    // Links are pairs of bodies that affect each other, like contacts and springs
+    pub(crate) fn update(
+        &self,
+        delta_time: f64,
+        bodies: &mut IdMap<Body>,
+        links: &[[BodyHandle; 2]],
+    ) {
+        if !self.enabled {
+            for body in bodies.values_mut() {
+                body.wake_up();
+            }
+            return;
+        }
+
+        for body in bodies.values_mut() {
+            if body.body_type != BodyType::Dynamic {
+                continue;
+            }
+
+            // Sleeping bodies are woken by being given a velocity
+            let moving = if body.sleeping {
+                self.is_moving(body)
+            } else {
+                self.has_moved(body, delta_time)
+            };
+
+            body.rest_pose = Pose::new(body.linear.position, body.angular.orientation);
+
+            if moving {
+                body.wake_up();
+            } else {
+                body.sleep_timer += delta_time;
+            }
+        }
+
+        // Only dynamic bodies join islands, static and kinematic ones would join everything
+        let mut islands = Islands::new();
+
+        for [a_id, b_id] in links {
+            let (Some(a), Some(b)) = (bodies.get(*a_id), bodies.get(*b_id)) else {
+                continue;
+            };
+
+            match (a.body_type, b.body_type) {
+                (BodyType::Dynamic, BodyType::Dynamic) => islands.join(*a_id, *b_id),
+                // Moving kinematic bodies keep what they touch awake
+                (BodyType::Kinematic, BodyType::Dynamic) if self.is_moving(a) => {
+                    bodies.get_mut(*b_id).unwrap().wake_up();
+                }
+                (BodyType::Dynamic, BodyType::Kinematic) if self.is_moving(b) => {
+                    bodies.get_mut(*a_id).unwrap().wake_up();
+                }
+                _ => {}
+            }
+        }
+
+        // An island sleeps once the body that has been resting least has rested long enough
+        let mut island_timers: HashMap<BodyHandle, f64> = HashMap::new();
+
+        for (id, body) in bodies.iter() {
+            if body.body_type == BodyType::Dynamic {
+                let timer = island_timers.entry(islands.root(id)).or_insert(f64::MAX);
+                *timer = timer.min(body.sleep_timer);
+            }
+        }
+
+        let ids: Vec<BodyHandle> = bodies.iter().map(|(id, _)| id).collect();
+
+        for id in ids {
+            let Some(&timer) = island_timers.get(&islands.root(id)) else {
+                continue;
+            };
+            let body = bodies.get_mut(id).unwrap();
+
+            if timer >= self.time_to_sleep {
+                body.put_to_sleep();
+            } else if body.is_sleeping() {
+                body.wake_up();
+            }
+        }
+    }
+}
+
+// Off by default as the thresholds depend on the units a world uses, turned on with new
+impl Default for SleepSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            linear_threshold: 0.0,
+            angular_threshold: 0.0,
+            time_to_sleep: 0.0,
+        }
+    }
+}
+
+// Union find over bodies, bodies never joined are their own island
+struct Islands {
+    parents: HashMap<BodyHandle, BodyHandle>,
+}
+
+impl Islands {
+    fn new() -> Self {
+        Self {
+            parents: HashMap::new(),
+        }
+    }
+
+    fn root(&mut self, id: BodyHandle) -> BodyHandle {
+        let mut root = id;
+
+        while let Some(&parent) = self.parents.get(&root) {
+            root = parent;
+        }
+
+        // Point everything on the way straight at the root
+        let mut current = id;
+        while current != root {
+            let parent = self.parents[&current];
+            self.parents.insert(current, root);
+            current = parent;
+        }
+
+        root
+    }
+
+    fn join(&mut self, a: BodyHandle, b: BodyHandle) {
+        let (a_root, b_root) = (self.root(a), self.root(b));
+
+        if a_root != b_root {
+            self.parents.insert(a_root, b_root);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    body::{Body, BodyHandle},
+    collision::{
+        BoxedCollisionPipeline, CollisionPipeline, default::DefaultCollisionPipeline,
+        sequential_impulse::SequentialImpulseResolver,
+    },
+    constraint::{
+        BoxedConstraint, Constraint, ConstraintSettings, distance::DistanceJoint,
+        prismatic::PrismaticJoint, pulley::PulleyJoint, revolute::RevoluteJoint, rope::RopeJoint,
+        weld::WeldJoint,
+    },
+    effector::{
+        BoxedEffector, ConstantAcceleration, ConstantForce, ConstantTorque, Drag, Effector,
+        Gravity, Spring,
+    },
+    id_map::IdMap,
+    integrator::{BoxedIntegrator, ExplicitEuler, Integrator, SemiImplicitEuler},
+    sleep::SleepSettings,
+    stage::{BoxedCustomStage, CustomStage, Stage},
+    timestep::{FixedTimestep, Pose},
+};
+
+// Full state of a world, restoring it gives back the same ids and free slots
+#[derive(Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub bodies: IdMap<Body>,
+    pub integrators: IdMap<SerializedObject>,
+    pub effectors: IdMap<SerializedObject>,
+    pub collision_pipelines: IdMap<SerializedObject>,
+    pub constraints: IdMap<SerializedObject>,
+    pub custom_stages: IdMap<SerializedObject>,
+    pub stages: Vec<Stage>,
+    pub substeps: u32,
+    pub timestep: FixedTimestep,
+    pub previous_poses: Vec<(BodyHandle, Pose)>,
+    pub sleep_settings: SleepSettings,
+    pub constraint_settings: ConstraintSettings,
+}
+
+// A boxed trait object stored as its registered name and its data
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializedObject {
+    pub type_name: String,
+    // Stored as RON as it keeps infinite values like the mass of static bodies
+    pub data: String,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    // The type was never registered so can't be saved
+    UnregisteredType(TypeId),
+    // No type was registered with the name so can't be loaded
+    UnknownTypeName(String),
+    Serialize(String),
+    Deserialize(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::UnregisteredType(type_id) => {
+                write!(f, "type {:?} is not registered", type_id)
+            }
+            SnapshotError::UnknownTypeName(name) => {
+                write!(f, "no type is registered with the name {}", name)
+            }
+            SnapshotError::Serialize(error) => write!(f, "failed to serialize: {}", error),
+            SnapshotError::Deserialize(error) => write!(f, "failed to deserialize: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+type SerializeFn = fn(&dyn Any) -> Result<String, SnapshotError>;
+type DeserializeFn<B> = fn(&str) -> Result<B, SnapshotError>;
+
+struct TypeRegistry<B> {
+    serializers: HashMap<TypeId, (String, SerializeFn)>,
+    deserializers: HashMap<String, DeserializeFn<B>>,
+}
+
+impl<B> TypeRegistry<B> {
+    fn new() -> Self {
+        Self {
+            serializers: HashMap::new(),
+            deserializers: HashMap::new(),
+        }
+    }
+
+    fn register<T: Serialize + 'static>(&mut self, name: &str, deserialize: DeserializeFn<B>) {
+        self.serializers
+            .insert(TypeId::of::<T>(), (name.to_string(), serialize::<T>));
+        self.deserializers.insert(name.to_string(), deserialize);
+    }
+
+    fn serialize(&self, value: &dyn Any) -> Result<SerializedObject, SnapshotError> {
+        let Some((name, serialize)) = self.serializers.get(&value.type_id()) else {
+            return Err(SnapshotError::UnregisteredType(value.type_id()));
+        };
+
+        Ok(SerializedObject {
+            type_name: name.clone(),
+            data: serialize(value)?,
+        })
+    }
+
+    fn deserialize(&self, object: &SerializedObject) -> Result<B, SnapshotError> {
+        match self.deserializers.get(&object.type_name) {
+            Some(deserialize) => deserialize(&object.data),
+            None => Err(SnapshotError::UnknownTypeName(object.type_name.clone())),
+        }
+    }
+}
+
+fn serialize<T: Serialize + 'static>(value: &dyn Any) -> Result<String, SnapshotError> {
+    // The registry only calls this with the type it was registered for
+    let value = value.downcast_ref::<T>().unwrap();
+    ron::to_string(value).map_err(|e| SnapshotError::Serialize(e.to_string()))
+}
+
+fn deserialize<T: DeserializeOwned>(data: &str) -> Result<T, SnapshotError> {
+    ron::from_str(data).map_err(|e| SnapshotError::Deserialize(e.to_string()))
+}
+
+fn deserialize_effector<T: Effector + Send + Sync + DeserializeOwned>(
+    data: &str,
+) -> Result<BoxedEffector, SnapshotError> {
+    Ok(Box::new(deserialize::<T>(data)?))
+}
+
+fn deserialize_integrator<T: Integrator + Send + Sync + DeserializeOwned>(
+    data: &str,
+) -> Result<BoxedIntegrator, SnapshotError> {
+    Ok(Box::new(deserialize::<T>(data)?))
+}
+
+fn deserialize_collision_pipeline<T: CollisionPipeline + Send + Sync + DeserializeOwned>(
+    data: &str,
+) -> Result<BoxedCollisionPipeline, SnapshotError> {
+    Ok(Box::new(deserialize::<T>(data)?))
+}
+
+fn deserialize_constraint<T: Constraint + Send + Sync + DeserializeOwned>(
+    data: &str,
+) -> Result<BoxedConstraint, SnapshotError> {
+    Ok(Box::new(deserialize::<T>(data)?))
+}
+
+fn deserialize_custom_stage<T: CustomStage + Send + Sync + DeserializeOwned>(
+    data: &str,
+) -> Result<BoxedCustomStage, SnapshotError> {
+    Ok(Box::new(deserialize::<T>(data)?))
+}
+
+// Maps the types behind boxed trait objects to names so they can be saved and loaded
+// User defined types have to be registered before taking or restoring a snapshot
+pub struct SnapshotRegistry {
+    effectors: TypeRegistry<BoxedEffector>,
+    integrators: TypeRegistry<BoxedIntegrator>,
+    collision_pipelines: TypeRegistry<BoxedCollisionPipeline>,
+    constraints: TypeRegistry<BoxedConstraint>,
+    custom_stages: TypeRegistry<BoxedCustomStage>,
+}
+
+impl SnapshotRegistry {
+    // Has all the built in types registered
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register_effector::<ConstantForce>("constant_force");
+        registry.register_effector::<ConstantAcceleration>("constant_acceleration");
+        registry.register_effector::<Gravity>("gravity");
+        registry.register_effector::<ConstantTorque>("constant_torque");
+        registry.register_effector::<Spring>("spring");
+        registry.register_effector::<Drag>("drag");
+
+        registry.register_integrator::<ExplicitEuler>("explicit_euler");
+        registry.register_integrator::<SemiImplicitEuler>("semi_implicit_euler");
+
+        registry.register_collision_pipeline::<DefaultCollisionPipeline>("default");
+        registry
+            .register_collision_pipeline::<DefaultCollisionPipeline<SequentialImpulseResolver>>(
+                "default_sequential_impulse",
+            );
+
+        registry.register_constraint::<DistanceJoint>("distance_joint");
+        registry.register_constraint::<PrismaticJoint>("prismatic_joint");
+        registry.register_constraint::<PulleyJoint>("pulley_joint");
+        registry.register_constraint::<RevoluteJoint>("revolute_joint");
+        registry.register_constraint::<RopeJoint>("rope_joint");
+        registry.register_constraint::<WeldJoint>("weld_joint");
+
+        registry
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            effectors: TypeRegistry::new(),
+            integrators: TypeRegistry::new(),
+            collision_pipelines: TypeRegistry::new(),
+            constraints: TypeRegistry::new(),
+            custom_stages: TypeRegistry::new(),
+        }
+    }
+
+    pub fn register_effector<T>(&mut self, name: &str)
+    where
+        T: Effector + Send + Sync + Serialize + DeserializeOwned,
+    {
+        self.effectors
+            .register::<T>(name, deserialize_effector::<T>);
+    }
+
+    pub fn register_integrator<T>(&mut self, name: &str)
+    where
+        T: Integrator + Send + Sync + Serialize + DeserializeOwned,
+    {
+        self.integrators
+            .register::<T>(name, deserialize_integrator::<T>);
+    }
+
+    pub fn register_collision_pipeline<T>(&mut self, name: &str)
+    where
+        T: CollisionPipeline + Send + Sync + Serialize + DeserializeOwned,
+    {
+        self.collision_pipelines
+            .register::<T>(name, deserialize_collision_pipeline::<T>);
+    }
+
+    pub fn register_constraint<T>(&mut self, name: &str)
+    where
+        T: Constraint + Send + Sync + Serialize + DeserializeOwned,
+    {
+        self.constraints
+            .register::<T>(name, deserialize_constraint::<T>);
+    }
+
+    pub fn register_custom_stage<T>(&mut self, name: &str)
+    where
+        T: CustomStage + Send + Sync + Serialize + DeserializeOwned,
+    {
+        self.custom_stages
+            .register::<T>(name, deserialize_custom_stage::<T>);
+    }
+
+    // Deref the box first so the type of the value inside is used
+    pub fn serialize_effector(
+        &self,
+        effector: &BoxedEffector,
+    ) -> Result<SerializedObject, SnapshotError> {
+        self.effectors.serialize((**effector).as_any())
+    }
+
+    pub fn deserialize_effector(
+        &self,
+        object: &SerializedObject,
+    ) -> Result<BoxedEffector, SnapshotError> {
+        self.effectors.deserialize(object)
+    }
+
+    pub fn serialize_integrator(
+        &self,
+        integrator: &BoxedIntegrator,
+    ) -> Result<SerializedObject, SnapshotError> {
+        self.integrators.serialize((**integrator).as_any())
+    }
+
+    pub fn deserialize_integrator(
+        &self,
+        object: &SerializedObject,
+    ) -> Result<BoxedIntegrator, SnapshotError> {
+        self.integrators.deserialize(object)
+    }
+
+    pub fn serialize_collision_pipeline(
+        &self,
+        collision_pipeline: &BoxedCollisionPipeline,
+    ) -> Result<SerializedObject, SnapshotError> {
+        self.collision_pipelines
+            .serialize((**collision_pipeline).as_any())
+    }
+
+    pub fn deserialize_collision_pipeline(
+        &self,
+        object: &SerializedObject,
+    ) -> Result<BoxedCollisionPipeline, SnapshotError> {
+        self.collision_pipelines.deserialize(object)
+    }
+
+    pub fn serialize_constraint(
+        &self,
+        constraint: &BoxedConstraint,
+    ) -> Result<SerializedObject, SnapshotError> {
+        self.constraints.serialize((**constraint).as_any())
+    }
+
+    pub fn deserialize_constraint(
+        &self,
+        object: &SerializedObject,
+    ) -> Result<BoxedConstraint, SnapshotError> {
+        self.constraints.deserialize(object)
+    }
+
+    pub fn serialize_custom_stage(
+        &self,
+        custom_stage: &BoxedCustomStage,
+    ) -> Result<SerializedObject, SnapshotError> {
+        self.custom_stages.serialize((**custom_stage).as_any())
+    }
+
+    pub fn deserialize_custom_stage(
+        &self,
+        object: &SerializedObject,
+    ) -> Result<BoxedCustomStage, SnapshotError> {
+        self.custom_stages.deserialize(object)
+    }
+}
+
+impl Default for SnapshotRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{AngularState, LinearState, Shape},
+        types::math::Vector,
+        world::World,
+    };
+
+    fn body_at(x: f64, y: f64, mass: f64) -> Body {
+        Body::new_rigid(
+            LinearState::new(Vector::new(x, y), Vector::zeros(), mass),
+            0.5,
+            AngularState::new(0.0, 0.0, 1000.0),
+            Shape::new_rectangle(Vector::new(20.0, 20.0)),
+        )
+    }
+
+    fn pose(world: &World, id: BodyHandle) -> Option<(Vector<f64>, Vector<f64>, f64, f64)> {
+        world.get_body(id).map(|body| {
+            (
+                body.linear.position,
+                body.linear.velocity,
+                body.angular.orientation,
+                body.angular.velocity,
+            )
+        })
+    }
+
+    #[test]
+    fn restoring_from_ron_gives_the_same_world() {
+        let mut world = World::new();
+        let ground = world.add_body(body_at(0.0, -50.0, f64::INFINITY));
+        let removed = world.add_body(body_at(100.0, 0.0, 1.0));
+        let falling = world.add_body(body_at(0.0, 0.0, 1.0));
+        let hanging = world.add_body(body_at(30.0, 40.0, 1.0));
+        let swinging = world.add_body(body_at(-30.0, 60.0, 1.0));
+        // Leaves a free slot the restored world has to keep
+        world.remove_body(removed);
+
+        let moving = vec![falling, hanging, swinging];
+        world.add_integrator(Box::new(SemiImplicitEuler::new(moving.clone())));
+        world.add_effector(Box::new(ConstantAcceleration::new(
+            moving,
+            Vector::new(0.0, -200.0),
+        )));
+        world.add_effector(Box::new(Spring::new([falling, hanging], 40.0, 50.0)));
+        let joint = DistanceJoint::new_auto_length(
+            [hanging, swinging],
+            [Vector::new(10.0, 0.0), Vector::zeros()],
+            &world,
+        )
+        .unwrap();
+        let joint = world.add_constraint(Box::new(joint));
+        world.add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(vec![
+            ground, falling, hanging,
+        ])));
+
+        for _ in 0..30 {
+            world.update(1.0 / 60.0);
+        }
+
+        let saved = ron::to_string(&world.snapshot().unwrap()).unwrap();
+        let mut restored = World::new();
+        restored.restore(&ron::from_str(&saved).unwrap()).unwrap();
+
+        // Both carry on exactly the same way
+        for _ in 0..30 {
+            world.update(1.0 / 60.0);
+            restored.update(1.0 / 60.0);
+        }
+
+        for id in [ground, removed, falling, hanging, swinging] {
+            assert_eq!(pose(&world, id), pose(&restored, id));
+        }
+
+        // Warm starting carries the joint's impulse across the restore
+        let impulse = |world: &World| {
+            let joint = (**world.get_constraint(joint).unwrap()).as_any();
+            joint.downcast_ref::<DistanceJoint>().unwrap().impulse()
+        };
+        assert_eq!(impulse(&world), impulse(&restored));
+
+        assert_eq!(
+            world.add_body(body_at(0.0, 100.0, 1.0)),
+            restored.add_body(body_at(0.0, 100.0, 1.0))
+        );
+    }
+}
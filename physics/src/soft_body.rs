@@ -1,13 +1,12 @@
 use crate::{
-    body::{Body, LinearState},
-    effector::Spring,
-    id_map::Id,
+    body::{Body, BodyHandle, LinearState},
+    effector::{EffectorHandle, Spring},
     world::World,
 };
 
 pub struct SoftBodyId {
-    pub points: Vec<Id>,
-    pub springs: Vec<Id>,
+    pub points: Vec<BodyHandle>,
+    pub springs: Vec<EffectorHandle>,
 }
 
 #[derive(Clone)]
@@ -41,13 +40,13 @@ impl World {
         points: Vec<LinearState>,
         springs: Vec<SoftBodySpring>,
     ) -> SoftBodyId {
-        let point_ids: Vec<Id> = points
+        let point_ids: Vec<BodyHandle> = points
             .clone()
             .into_iter()
             .map(|l| self.add_body(Body::new_particle(l, 1.0)))
             .collect();
 
-        let spring_ids: Vec<Id> = springs
+        let spring_ids: Vec<EffectorHandle> = springs
             .into_iter()
             .map(|s| {
                 self.add_effector(Box::new(Spring::new(
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use std::any::Any;
+
+use crate::{
+    body::Body,
+    id_map::{Id, IdMap},
+    types::AsAny,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stage {
+    ApplyEffectors,
+    Integrate,
+    HandleCollisions,
+    SolveConstraints,
+    UpdateSleeping,
+    // Handle of a stage added with World::add_custom_stage
+    Custom(CustomStageHandle),
+}
+
+impl Stage {
+    pub fn default_order() -> Vec<Stage> {
+        vec![
+            Stage::ApplyEffectors,
+            Stage::Integrate,
+            Stage::HandleCollisions,
+            Stage::SolveConstraints,
+            Stage::UpdateSleeping,
+        ]
+    }
+}
+
+pub trait CustomStage: Any + AsAny {
+    fn run(&mut self, delta_time: f64, bodies: &mut IdMap<Body>);
+}
+
+pub type BoxedCustomStage = Box<dyn CustomStage + Send + Sync>;
+pub type CustomStageHandle = Id<BoxedCustomStage>;
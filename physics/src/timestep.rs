@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::math::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FixedTimestep {
+    pub delta_time: f64,
+    // Stops the simulation falling further behind after a long frame
+    pub max_substeps: u32,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(delta_time: f64, max_substeps: u32) -> Self {
+        Self {
+            delta_time,
+            max_substeps,
+            accumulator: 0.0,
+        }
+    }
+
+    // Returns the number of fixed steps to take
+    pub fn accumulate(&mut self, elapsed_time: f64) -> u32 {
+        self.accumulator += elapsed_time.max(0.0);
+
+        let steps = (self.accumulator / self.delta_time).floor() as u32;
+
+        if steps > self.max_substeps {
+            // Drop the time that can't be caught up on
+            self.accumulator %= self.delta_time;
+            self.max_substeps
+        } else {
+            self.accumulator -= steps as f64 * self.delta_time;
+            steps
+        }
+    }
+
+    // How far between the last two steps the current time is
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.delta_time).clamp(0.0, 1.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(1.0 / 60.0, 8)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Pose {
+    pub position: Vector<f64>,
+    pub orientation: f64,
+}
+
+impl Pose {
+    pub fn new(position: Vector<f64>, orientation: f64) -> Self {
+        Self {
+            position,
+            orientation,
+        }
+    }
+
+    pub fn interpolate(&self, other: &Pose, alpha: f64) -> Pose {
+        Pose {
+            position: self.position.lerp(&other.position, alpha),
+            orientation: self.orientation + (other.orientation - self.orientation) * alpha,
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::any::Any;
 
 pub mod math {
     pub type Vector<T> = nalgebra::Vector2<T>;
+    pub type Matrix<T> = nalgebra::Matrix2<T>;
     pub type Point<T> = nalgebra::Point2<T>;
     pub type Translation<T> = nalgebra::Translation2<T>;
     pub type Rotation<T> = nalgebra::Rotation2<T>;
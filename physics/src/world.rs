@@ -1,17 +1,36 @@
 use crate::{
-    body::{AngularState, Body, LinearState, Shape},
-    collision::CollisionPipeline,
-    effector::Effector,
-    id_map::{Id, IdMap},
-    integrator::{self, Integrator},
+    body::{AngularState, Body, BodyHandle, LinearState, Shape},
+    collision::{BoxedCollisionPipeline, CollisionEvent, PipelineHandle},
+    constraint::{BoxedConstraint, ConstraintBreakEvent, ConstraintHandle, ConstraintSettings},
+    effector::{BoxedEffector, EffectorHandle},
+    id_map::IdMap,
+    integrator::{self, BoxedIntegrator, IntegratorHandle},
+    sleep::SleepSettings,
+    snapshot::{SnapshotError, SnapshotRegistry, WorldSnapshot},
+    stage::{BoxedCustomStage, CustomStageHandle, Stage},
+    timestep::{FixedTimestep, Pose},
     types::math::Vector,
 };
+use std::collections::HashMap;
 
 pub struct World {
     bodies: IdMap<Body>,
-    integrators: IdMap<Box<dyn Integrator + Send + Sync>>,
-    effectors: IdMap<Box<dyn Effector + Send + Sync>>,
-    collision_pipelines: IdMap<Box<dyn CollisionPipeline + Send + Sync>>,
+    integrators: IdMap<BoxedIntegrator>,
+    effectors: IdMap<BoxedEffector>,
+    collision_pipelines: IdMap<BoxedCollisionPipeline>,
+    constraints: IdMap<BoxedConstraint>,
+    custom_stages: IdMap<BoxedCustomStage>,
+    // Order update runs the stages in
+    stages: Vec<Stage>,
+    substeps: u32,
+    timestep: FixedTimestep,
+    // Poses before the latest fixed step, used for interpolation
+    previous_poses: HashMap<BodyHandle, Pose>,
+    sleep_settings: SleepSettings,
+    constraint_settings: ConstraintSettings,
+    // Joints that broke since the last drain
+    constraint_events: Vec<ConstraintBreakEvent>,
+    snapshot_registry: SnapshotRegistry,
 }
 
 impl World {
@@ -21,35 +40,63 @@ impl World {
             integrators: IdMap::new(),
             effectors: IdMap::new(),
             collision_pipelines: IdMap::new(),
+            constraints: IdMap::new(),
+            custom_stages: IdMap::new(),
+            stages: Stage::default_order(),
+            substeps: 1,
+            timestep: FixedTimestep::default(),
+            previous_poses: HashMap::new(),
+            sleep_settings: SleepSettings::default(),
+            constraint_settings: ConstraintSettings::default(),
+            constraint_events: Vec::new(),
+            snapshot_registry: SnapshotRegistry::new(),
         }
     }
 
-    pub fn add_body(&mut self, body: Body) -> Id {
+    pub fn add_body(&mut self, body: Body) -> BodyHandle {
         self.bodies.add(body)
     }
 
-    pub fn remove_body(&mut self, id: Id) {
+    // Also removes the body from everything holding it
+    pub fn remove_body(&mut self, id: BodyHandle) {
+        if !self.bodies.contains(id) {
+            return;
+        }
+
         self.bodies.remove(id);
+        self.previous_poses.remove(&id);
+
+        self.integrators.retain(|_, i| i.body_removed(id));
+        self.effectors.retain(|_, e| e.body_removed(id));
+        self.collision_pipelines.retain(|_, p| p.body_removed(id));
+        self.constraints.retain(|_, c| c.body_removed(id));
     }
 
     pub fn clear_bodies(&mut self) {
-        self.bodies.clear();
+        let ids: Vec<BodyHandle> = self.bodies.iter().map(|(id, _)| id).collect();
+
+        for id in ids {
+            self.remove_body(id);
+        }
     }
 
-    pub fn get_body(&self, id: Id) -> Option<&Body> {
+    pub fn get_body(&self, id: BodyHandle) -> Option<&Body> {
         self.bodies.get(id)
     }
 
-    pub fn get_body_mut(&mut self, id: Id) -> Option<&mut Body> {
+    pub fn get_body_mut(&mut self, id: BodyHandle) -> Option<&mut Body> {
         self.bodies.get_mut(id)
     }
 
-    // Is there a way to not keep doing + Send + Sync
-    pub fn add_integrator(&mut self, integrator: Box<dyn Integrator + Send + Sync>) -> Id {
+    pub fn iter_bodies(&self) -> impl Iterator<Item = (BodyHandle, &Body)> {
+        self.bodies.iter()
+    }
+
+    pub fn add_integrator(&mut self, integrator: BoxedIntegrator) -> IntegratorHandle {
         self.integrators.add(integrator)
     }
 
-    pub fn remove_integrator(&mut self, id: Id) {
+    pub fn remove_integrator(&mut self, id: IntegratorHandle) {
         self.integrators.remove(id);
     }
 
@@ -57,14 +104,55 @@ impl World {
         self.integrators.clear();
     }
 
-    pub fn get_integrator(&self, id: Id) -> Option<&Box<dyn Integrator + Send + Sync>> {
+    pub fn get_integrator(&self, id: IntegratorHandle) -> Option<&BoxedIntegrator> {
         self.integrators.get(id)
     }
 
-    pub fn get_integrator_mut(&mut self, id: Id) -> Option<&mut Box<dyn Integrator + Send + Sync>> {
+    pub fn get_integrator_mut(&mut self, id: IntegratorHandle) -> Option<&mut BoxedIntegrator> {
         self.integrators.get_mut(id)
     }
 
+    // Returns false if the integrator doesn't exist or doesn't hold a list of bodies
+    pub fn add_body_to_integrator(
+        &mut self,
+        integrator: IntegratorHandle,
+        body: BodyHandle,
+    ) -> bool {
+        if !self.bodies.contains(body) {
+            return false;
+        }
+
+        match self
+            .integrators
+            .get_mut(integrator)
+            .and_then(|i| i.as_body_group_mut())
+        {
+            Some(group) => {
+                group.add_body(body);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_body_from_integrator(
+        &mut self,
+        integrator: IntegratorHandle,
+        body: BodyHandle,
+    ) -> bool {
+        match self
+            .integrators
+            .get_mut(integrator)
+            .and_then(|i| i.as_body_group_mut())
+        {
+            Some(group) => {
+                group.remove_body(body);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn step(&mut self, delta_time: f64) {
         for integrator in self.integrators.values_mut() {
             integrator.step(delta_time, &mut self.bodies);
@@ -77,11 +165,134 @@ impl World {
         }
     }
 
-    pub fn add_effector(&mut self, effector: Box<dyn Effector + Send + Sync>) -> Id {
+    pub fn add_custom_stage(&mut self, stage: BoxedCustomStage) -> CustomStageHandle {
+        self.custom_stages.add(stage)
+    }
+
+    // Runs before every other stage
+    pub fn add_pre_step_stage(&mut self, stage: BoxedCustomStage) -> CustomStageHandle {
+        let id = self.add_custom_stage(stage);
+        self.stages.insert(0, Stage::Custom(id));
+        id
+    }
+
+    // Runs after every other stage
+    pub fn add_post_step_stage(&mut self, stage: BoxedCustomStage) -> CustomStageHandle {
+        let id = self.add_custom_stage(stage);
+        self.stages.push(Stage::Custom(id));
+        id
+    }
+
+    pub fn remove_custom_stage(&mut self, id: CustomStageHandle) {
+        self.custom_stages.remove(id);
+        self.stages.retain(|s| *s != Stage::Custom(id));
+    }
+
+    pub fn clear_custom_stages(&mut self) {
+        self.custom_stages.clear();
+        self.stages.retain(|s| !matches!(s, Stage::Custom(_)));
+    }
+
+    pub fn get_custom_stage(&self, id: CustomStageHandle) -> Option<&BoxedCustomStage> {
+        self.custom_stages.get(id)
+    }
+
+    pub fn get_custom_stage_mut(&mut self, id: CustomStageHandle) -> Option<&mut BoxedCustomStage> {
+        self.custom_stages.get_mut(id)
+    }
+
+    pub fn get_stages(&self) -> &Vec<Stage> {
+        &self.stages
+    }
+
+    pub fn set_stages(&mut self, stages: Vec<Stage>) {
+        self.stages = stages;
+    }
+
+    pub fn get_substeps(&self) -> u32 {
+        self.substeps
+    }
+
+    pub fn set_substeps(&mut self, substeps: u32) {
+        self.substeps = substeps.max(1);
+    }
+
+    // Runs every stage in order, split into substeps
+    pub fn update(&mut self, delta_time: f64) {
+        let substep_delta_time = delta_time / self.substeps as f64;
+
+        for _ in 0..self.substeps {
+            for i in 0..self.stages.len() {
+                self.run_stage(self.stages[i], substep_delta_time);
+            }
+        }
+    }
+
+    fn run_stage(&mut self, stage: Stage, delta_time: f64) {
+        match stage {
+            Stage::ApplyEffectors => self.apply_effectors(),
+            Stage::Integrate => self.step(delta_time),
+            Stage::HandleCollisions => self.handle_collisions(),
+            Stage::SolveConstraints => self.solve_constraints(delta_time),
+            Stage::UpdateSleeping => self.update_sleeping(delta_time),
+            Stage::Custom(id) => {
+                if let Some(stage) = self.custom_stages.get_mut(id) {
+                    stage.run(delta_time, &mut self.bodies);
+                }
+            }
+        }
+    }
+
+    pub fn get_fixed_timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    pub fn get_fixed_timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.timestep
+    }
+
+    // Advances by real elapsed time in fixed steps and returns the interpolation alpha
+    pub fn step_fixed(&mut self, elapsed_time: f64) -> f64 {
+        let steps = self.timestep.accumulate(elapsed_time);
+
+        for i in 0..steps {
+            if i == steps - 1 {
+                self.store_previous_poses();
+            }
+
+            self.update(self.timestep.delta_time);
+        }
+
+        self.timestep.alpha()
+    }
+
+    fn store_previous_poses(&mut self) {
+        self.previous_poses.clear();
+
+        for (id, body) in self.bodies.iter() {
+            self.previous_poses.insert(
+                id,
+                Pose::new(body.linear.position, body.angular.orientation),
+            );
+        }
+    }
+
+    // Pose between the last two fixed steps for rendering
+    pub fn get_interpolated_pose(&self, id: BodyHandle, alpha: f64) -> Option<Pose> {
+        let body = self.bodies.get(id)?;
+        let current = Pose::new(body.linear.position, body.angular.orientation);
+
+        match self.previous_poses.get(&id) {
+            Some(previous) => Some(previous.interpolate(&current, alpha)),
+            None => Some(current),
+        }
+    }
+
+    pub fn add_effector(&mut self, effector: BoxedEffector) -> EffectorHandle {
         self.effectors.add(effector)
     }
 
-    pub fn remove_effector(&mut self, id: Id) {
+    pub fn remove_effector(&mut self, id: EffectorHandle) {
         self.effectors.remove(id);
     }
 
@@ -89,14 +300,51 @@ impl World {
         self.effectors.clear();
     }
 
-    pub fn get_effector(&self, id: Id) -> Option<&Box<dyn Effector + Send + Sync>> {
+    pub fn get_effector(&self, id: EffectorHandle) -> Option<&BoxedEffector> {
         self.effectors.get(id)
     }
 
-    pub fn get_effector_mut(&mut self, id: Id) -> Option<&mut Box<dyn Effector + Send + Sync>> {
+    pub fn get_effector_mut(&mut self, id: EffectorHandle) -> Option<&mut BoxedEffector> {
         self.effectors.get_mut(id)
     }
 
+    // Returns false if the effector doesn't exist or doesn't hold a list of bodies
+    pub fn add_body_to_effector(&mut self, effector: EffectorHandle, body: BodyHandle) -> bool {
+        if !self.bodies.contains(body) {
+            return false;
+        }
+
+        match self
+            .effectors
+            .get_mut(effector)
+            .and_then(|e| e.as_body_group_mut())
+        {
+            Some(group) => {
+                group.add_body(body);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_body_from_effector(
+        &mut self,
+        effector: EffectorHandle,
+        body: BodyHandle,
+    ) -> bool {
+        match self
+            .effectors
+            .get_mut(effector)
+            .and_then(|e| e.as_body_group_mut())
+        {
+            Some(group) => {
+                group.remove_body(body);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn apply_effectors(&mut self) {
         for effector in self.effectors.values_mut() {
             effector.apply(&mut self.bodies);
@@ -105,13 +353,13 @@ impl World {
 
     pub fn add_collision_pipeline(
         &mut self,
-        mut collision_pipeline: Box<dyn CollisionPipeline + Send + Sync>,
-    ) -> Id {
+        mut collision_pipeline: BoxedCollisionPipeline,
+    ) -> PipelineHandle {
         collision_pipeline.init(&mut self.bodies);
         self.collision_pipelines.add(collision_pipeline)
     }
 
-    pub fn remove_collision_pipeline(&mut self, id: Id) {
+    pub fn remove_collision_pipeline(&mut self, id: PipelineHandle) {
         self.collision_pipelines.remove(id);
     }
 
@@ -119,30 +367,287 @@ impl World {
         self.collision_pipelines.clear();
     }
 
-    pub fn get_collision_pipeline(
-        &self,
-        id: Id,
-    ) -> Option<&Box<dyn CollisionPipeline + Send + Sync>> {
+    pub fn get_collision_pipeline(&self, id: PipelineHandle) -> Option<&BoxedCollisionPipeline> {
         self.collision_pipelines.get(id)
     }
 
     pub fn get_collision_pipeline_mut(
         &mut self,
-        id: Id,
-    ) -> Option<&mut Box<dyn CollisionPipeline + Send + Sync>> {
+        id: PipelineHandle,
+    ) -> Option<&mut BoxedCollisionPipeline> {
         self.collision_pipelines.get_mut(id)
     }
 
+    // Returns false if the pipeline doesn't exist or doesn't hold a list of bodies
+    pub fn add_body_to_collision_pipeline(
+        &mut self,
+        collision_pipeline: PipelineHandle,
+        body: BodyHandle,
+    ) -> bool {
+        if !self.bodies.contains(body) {
+            return false;
+        }
+
+        match self
+            .collision_pipelines
+            .get_mut(collision_pipeline)
+            .and_then(|p| p.as_body_group_mut())
+        {
+            Some(group) => {
+                group.add_body(body);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_body_from_collision_pipeline(
+        &mut self,
+        collision_pipeline: PipelineHandle,
+        body: BodyHandle,
+    ) -> bool {
+        match self
+            .collision_pipelines
+            .get_mut(collision_pipeline)
+            .and_then(|p| p.as_body_group_mut())
+        {
+            Some(group) => {
+                group.remove_body(body);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn handle_collisions(&mut self) {
         for pipeline in self.collision_pipelines.values_mut() {
             pipeline.handle(&mut self.bodies);
         }
     }
 
+    pub fn add_constraint(&mut self, constraint: BoxedConstraint) -> ConstraintHandle {
+        self.constraints.add(constraint)
+    }
+
+    pub fn remove_constraint(&mut self, id: ConstraintHandle) {
+        self.constraints.remove(id);
+    }
+
+    pub fn clear_constraints(&mut self) {
+        self.constraints.clear();
+    }
+
+    pub fn get_constraint(&self, id: ConstraintHandle) -> Option<&BoxedConstraint> {
+        self.constraints.get(id)
+    }
+
+    pub fn get_constraint_mut(&mut self, id: ConstraintHandle) -> Option<&mut BoxedConstraint> {
+        self.constraints.get_mut(id)
+    }
+
+    pub fn get_constraint_settings(&self) -> &ConstraintSettings {
+        &self.constraint_settings
+    }
+
+    pub fn get_constraint_settings_mut(&mut self) -> &mut ConstraintSettings {
+        &mut self.constraint_settings
+    }
+
+    pub fn solve_constraints(&mut self, delta_time: f64) {
+        // Constraints between resting bodies are left alone so they can stay asleep
+        let active: Vec<ConstraintHandle> = self
+            .constraints
+            .iter()
+            .filter(|(_, constraint)| {
+                !constraint
+                    .bodies()
+                    .iter()
+                    .all(|id| self.bodies.get(*id).is_some_and(|body| body.is_resting()))
+            })
+            .map(|(id, _)| id)
+            .collect();
+
+        for id in &active {
+            let constraint = self.constraints.get_mut(*id).unwrap();
+            constraint.prepare(delta_time, &self.constraint_settings, &mut self.bodies);
+        }
+
+        for _ in 0..self.constraint_settings.iterations {
+            for id in &active {
+                self.constraints
+                    .get_mut(*id)
+                    .unwrap()
+                    .solve(&mut self.bodies);
+            }
+        }
+
+        for _ in 0..self.constraint_settings.position_iterations {
+            for id in &active {
+                self.constraints
+                    .get_mut(*id)
+                    .unwrap()
+                    .solve_positions(&self.constraint_settings, &mut self.bodies);
+            }
+        }
+
+        for id in active {
+            let constraint = self.constraints.get(id).unwrap();
+            let impulse = constraint.reaction_impulse();
+            let angular_impulse = constraint.reaction_angular_impulse();
+
+            // Linear and angular impulses are in different units so each has its own threshold
+            let exceeds = |threshold: Option<f64>, impulse: f64| {
+                threshold.is_some_and(|threshold| impulse > threshold)
+            };
+
+            if exceeds(constraint.break_threshold(), impulse)
+                || exceeds(constraint.angular_break_threshold(), angular_impulse)
+            {
+                self.constraint_events.push(ConstraintBreakEvent {
+                    constraint: id,
+                    bodies: constraint.bodies(),
+                    impulse,
+                    angular_impulse,
+                });
+                self.constraints.remove(id);
+            }
+        }
+    }
+
+    pub fn get_sleep_settings(&self) -> &SleepSettings {
+        &self.sleep_settings
+    }
+
+    pub fn get_sleep_settings_mut(&mut self) -> &mut SleepSettings {
+        &mut self.sleep_settings
+    }
+
+    // Puts resting islands of bodies to sleep and wakes islands something touched
+    pub fn update_sleeping(&mut self, delta_time: f64) {
+        let mut links = Vec::new();
+
+        for pipeline in self.collision_pipelines.values() {
+            links.extend(pipeline.links());
+        }
+
+        for effector in self.effectors.values() {
+            links.extend(effector.links());
+        }
+
+        for constraint in self.constraints.values() {
+            links.push(constraint.bodies());
+        }
+
+        self.sleep_settings
+            .update(delta_time, &mut self.bodies, &links);
+    }
+
+    pub fn get_snapshot_registry(&self) -> &SnapshotRegistry {
+        &self.snapshot_registry
+    }
+
+    // Register user defined types here so they can be in snapshots
+    pub fn get_snapshot_registry_mut(&mut self) -> &mut SnapshotRegistry {
+        &mut self.snapshot_registry
+    }
+
+    pub fn snapshot(&self) -> Result<WorldSnapshot, SnapshotError> {
+        let registry = &self.snapshot_registry;
+
+        // Sorted so the same world always gives the same snapshot
+        let mut previous_poses: Vec<(BodyHandle, Pose)> = self
+            .previous_poses
+            .iter()
+            .map(|(id, pose)| (*id, *pose))
+            .collect();
+        previous_poses.sort_by_key(|(id, _)| id.index);
+
+        Ok(WorldSnapshot {
+            bodies: self.bodies.clone(),
+            integrators: self
+                .integrators
+                .try_map(|i| registry.serialize_integrator(i))?,
+            effectors: self.effectors.try_map(|e| registry.serialize_effector(e))?,
+            collision_pipelines: self
+                .collision_pipelines
+                .try_map(|p| registry.serialize_collision_pipeline(p))?,
+            constraints: self
+                .constraints
+                .try_map(|c| registry.serialize_constraint(c))?,
+            custom_stages: self
+                .custom_stages
+                .try_map(|s| registry.serialize_custom_stage(s))?,
+            stages: self.stages.clone(),
+            substeps: self.substeps,
+            timestep: self.timestep.clone(),
+            previous_poses,
+            sleep_settings: self.sleep_settings.clone(),
+            constraint_settings: self.constraint_settings.clone(),
+        })
+    }
+
+    // Replaces the whole simulation, nothing changes if it fails
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> Result<(), SnapshotError> {
+        let registry = &self.snapshot_registry;
+
+        let integrators = snapshot
+            .integrators
+            .try_map(|i| registry.deserialize_integrator(i))?;
+        let effectors = snapshot
+            .effectors
+            .try_map(|e| registry.deserialize_effector(e))?;
+        let collision_pipelines = snapshot
+            .collision_pipelines
+            .try_map(|p| registry.deserialize_collision_pipeline(p))?;
+        let constraints = snapshot
+            .constraints
+            .try_map(|c| registry.deserialize_constraint(c))?;
+        let custom_stages = snapshot
+            .custom_stages
+            .try_map(|s| registry.deserialize_custom_stage(s))?;
+
+        self.bodies = snapshot.bodies.clone();
+        self.integrators = integrators;
+        self.effectors = effectors;
+        self.collision_pipelines = collision_pipelines;
+        self.constraints = constraints;
+        self.custom_stages = custom_stages;
+        self.stages = snapshot.stages.clone();
+        self.substeps = snapshot.substeps;
+        self.timestep = snapshot.timestep.clone();
+        self.previous_poses = snapshot.previous_poses.iter().copied().collect();
+        self.sleep_settings = snapshot.sleep_settings.clone();
+        self.constraint_settings = snapshot.constraint_settings.clone();
+
+        Ok(())
+    }
+
+    // Collision events from every pipeline since the last drain, nothing else clears them so
+    // drain them regularly even if they aren't used
+    pub fn drain_collision_events(&mut self) -> Vec<CollisionEvent> {
+        let mut events = Vec::new();
+
+        for pipeline in self.collision_pipelines.values_mut() {
+            events.extend(pipeline.drain_events());
+        }
+
+        events
+    }
+
+    // Joints broken since the last drain
+    pub fn drain_constraint_events(&mut self) -> Vec<ConstraintBreakEvent> {
+        std::mem::take(&mut self.constraint_events)
+    }
+
     pub fn reset(&mut self) {
+        self.clear_custom_stages();
         self.clear_collision_pipelines();
+        self.clear_constraints();
+        self.constraint_events.clear();
         self.clear_effectors();
         self.clear_integrators();
         self.clear_bodies();
+        self.timestep.reset();
+        self.sleep_settings = SleepSettings::default();
     }
 }
@@ -19,7 +19,7 @@ use physics::{
     body::{AngularState, Body, LinearState, Shape},
     collision::default::{DefaultCollisionPipeline, DefaultNarrowPhase},
     effector::{ConstantAcceleration, Drag, Gravity, Spring},
-    id_map::{Id, IdMap},
+    id_map::IdMap,
     integrator::SemiImplicitEuler,
     soft_body::{self, SoftBodyId, SoftBodySpring},
     types::math::*,
@@ -77,19 +77,20 @@ fn update_physics(
 ) {
     let physics_world = &mut physics_world.world;
 
-    physics_world.apply_effectors();
-    physics_world.step(time.delta_secs_f64());
-    physics_world.handle_collisions();
+    let alpha = physics_world.step_fixed(time.delta_secs_f64());
+
+    // Nothing reacts to collisions or broken joints yet, drain them so they don't build up
+    physics_world.drain_collision_events();
+    physics_world.drain_constraint_events();
 
     for (body_id, mut transform) in body_query.iter_mut() {
         let BodyId(id) = body_id;
-        let body = physics_world.get_body(*id).unwrap();
+        let pose = physics_world.get_interpolated_pose(*id, alpha).unwrap();
 
-        let position = body.linear.position;
-        transform.translation.x = position.x as f32;
-        transform.translation.y = position.y as f32;
+        transform.translation.x = pose.position.x as f32;
+        transform.translation.y = pose.position.y as f32;
 
-        transform.rotation = Quat::from_rotation_z(body.angular.orientation as f32);
+        transform.rotation = Quat::from_rotation_z(pose.orientation as f32);
     }
 
     for (effector_id, mut transform) in spring_query.iter_mut() {
@@ -104,8 +105,14 @@ fn update_physics(
             .unwrap();
         let [a_id, b_id] = spring.bodies;
         let (a_position, b_position) = (
-            physics_world.get_body(a_id).unwrap().linear.position,
-            physics_world.get_body(b_id).unwrap().linear.position,
+            physics_world
+                .get_interpolated_pose(a_id, alpha)
+                .unwrap()
+                .position,
+            physics_world
+                .get_interpolated_pose(b_id, alpha)
+                .unwrap()
+                .position,
         );
 
         let length = a_position.metric_distance(&b_position);
@@ -6,10 +6,10 @@ use bevy::{
 };
 use i_triangle::float::triangulatable::Triangulatable;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
+    body::{AngularState, Body, BodyHandle, LinearState, Shape},
     collision::default::{DefaultCollisionPipeline, DefaultNarrowPhase},
-    effector::{ConstantAcceleration, Drag, Gravity, Spring},
-    id_map::{Id, IdMap},
+    effector::{ConstantAcceleration, Drag, EffectorHandle, Gravity, Spring},
+    id_map::IdMap,
     integrator::SemiImplicitEuler,
     soft_body::{self, SoftBodyId, SoftBodySpring},
     types::math::*,
@@ -28,10 +28,10 @@ pub struct PhysicsWorld {
 
 // Make enum?
 #[derive(Component)]
-pub struct BodyId(pub Id);
+pub struct BodyId(pub BodyHandle);
 
 #[derive(Component)]
-pub struct EffectorId(pub Id);
+pub struct EffectorId(pub EffectorHandle);
 
 #[derive(Component)]
 pub struct SpringEffector;
@@ -68,7 +68,7 @@ pub fn spawn_physics_body(
     physics_world: &mut ResMut<PhysicsWorld>,
     body: Body,
     colour: Color,
-) -> Id {
+) -> BodyHandle {
     let id = physics_world.world.add_body(body.clone());
 
     commands.spawn((
@@ -200,6 +200,30 @@ pub fn handle_load_scene(
                 &mut materials,
                 &mut physics_world,
             ),
+            PhysicsScene::Chain => scenes::chain::load(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut physics_world,
+            ),
+            PhysicsScene::Joints => scenes::joints::load(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut physics_world,
+            ),
+            PhysicsScene::Bridge => scenes::bridge::load(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut physics_world,
+            ),
+            PhysicsScene::Ropes => scenes::ropes::load(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut physics_world,
+            ),
         }
     }
 }
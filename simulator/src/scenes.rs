@@ -1,9 +1,13 @@
+pub mod bridge;
+pub mod chain;
 pub mod circle_collision;
 pub mod collision_spring;
 pub mod falling_circles;
 pub mod falling_rectangles;
+pub mod joints;
 pub mod orbit;
 pub mod polygon;
+pub mod ropes;
 pub mod spring;
 pub mod tower;
 
@@ -17,4 +21,8 @@ pub enum PhysicsScene {
     CollisionSpring,
     Polygon,
     Orbit,
+    Chain,
+    Joints,
+    Bridge,
+    Ropes,
 }
@@ -0,0 +1,139 @@
+use std::f64;
+
+use bevy::prelude::*;
+use physics::{
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
+    collision::{CollisionFilter, default::DefaultCollisionPipeline},
+    constraint::weld::WeldJoint,
+    effector::ConstantAcceleration,
+    integrator::SemiImplicitEuler,
+    types::math::Vector,
+};
+use rand::Rng;
+
+use crate::physics_helpers::{PhysicsWorld, spawn_physics_body};
+
+pub fn load(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    physics_world: &mut ResMut<PhysicsWorld>,
+) {
+    let mut rng = rand::rng();
+
+    let mut pillars: Vec<BodyHandle> = Vec::new();
+
+    for x in [-360.0, 360.0] {
+        pillars.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_rigid(
+                LinearState::new(Vector::new(x, -300.0), Vector::zeros(), f64::INFINITY),
+                0.3,
+                AngularState::new(0.0, 0.0, f64::INFINITY),
+                Shape::new_rectangle(Vector::new(60.0, 400.0)),
+            )
+            .with_friction(0.6, 0.4)
+            .with_body_type(BodyType::Static),
+            Color::WHITE,
+        ));
+    }
+
+    // Planks welded end to end, hard enough hits snap the welds
+    let mut planks: Vec<BodyHandle> = Vec::new();
+    let mut previous = pillars[0];
+
+    for i in 0..11 {
+        let x = -300.0 + 60.0 * i as f64;
+
+        let plank = spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(x, -110.0),
+                0.0,
+                0.001,
+                0.3,
+                Shape::new_rectangle(Vector::new(60.0, 20.0)),
+            )
+            .with_friction(0.6, 0.4)
+            .with_collision_filter(CollisionFilter::default().with_group(1)),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        );
+
+        let weld = WeldJoint::new_at_point(
+            [previous, plank],
+            Vector::new(x - 30.0, -110.0),
+            &physics_world.world,
+        )
+        .unwrap()
+        .with_break_threshold(100.0);
+        physics_world.world.add_constraint(Box::new(weld));
+
+        planks.push(plank);
+        previous = plank;
+    }
+
+    let weld = WeldJoint::new_at_point(
+        [previous, pillars[1]],
+        Vector::new(330.0, -110.0),
+        &physics_world.world,
+    )
+    .unwrap()
+    .with_break_threshold(100.0);
+    physics_world.world.add_constraint(Box::new(weld));
+
+    let mut balls: Vec<BodyHandle> = Vec::new();
+
+    for _ in 0..10 {
+        balls.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-300.0..300.0),
+                    rng.random_range(0.0..1500.0),
+                ),
+                0.0,
+                0.001,
+                0.3,
+                Shape::new_circle(rng.random_range(10.0..30.0)),
+            )
+            .with_friction(0.6, 0.4),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        ));
+    }
+
+    let bodies = [planks, balls].concat();
+
+    physics_world
+        .world
+        .add_integrator(Box::new(SemiImplicitEuler::new(bodies.clone())));
+
+    physics_world
+        .world
+        .add_effector(Box::new(ConstantAcceleration::new(
+            bodies.clone(),
+            Vector::new(0.0, -200.0),
+        )));
+
+    physics_world
+        .world
+        .add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(
+            [bodies, pillars].concat(),
+        )));
+}
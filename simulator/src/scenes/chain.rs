@@ -0,0 +1,136 @@
+use std::f64;
+
+use bevy::prelude::*;
+use physics::{
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
+    collision::{CollisionFilter, default::DefaultCollisionPipeline},
+    constraint::distance::DistanceJoint,
+    effector::ConstantAcceleration,
+    integrator::SemiImplicitEuler,
+    types::math::Vector,
+};
+use rand::Rng;
+
+use crate::physics_helpers::{PhysicsWorld, spawn_physics_body};
+
+pub fn load(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    physics_world: &mut ResMut<PhysicsWorld>,
+) {
+    let mut rng = rand::rng();
+
+    let pivot = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_particle(
+            LinearState::new(Vector::new(-300.0, 300.0), Vector::zeros(), f64::INFINITY),
+            1.0,
+        )
+        .with_body_type(BodyType::Static),
+        Color::WHITE,
+    );
+
+    // Links share a group so the ones next to each other don't collide at the joints
+    let mut links: Vec<BodyHandle> = Vec::new();
+    let mut previous = (pivot, Vector::zeros());
+
+    for i in 0..15 {
+        let link = spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(-270.0 + 50.0 * i as f64, 300.0),
+                0.0,
+                0.001,
+                0.5,
+                Shape::new_rectangle(Vector::new(40.0, 10.0)),
+            )
+            .with_friction(0.6, 0.4)
+            .with_collision_filter(CollisionFilter::default().with_group(1)),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        );
+
+        let joint = DistanceJoint::new_auto_length(
+            [previous.0, link],
+            [previous.1, Vector::new(-20.0, 0.0)],
+            &physics_world.world,
+        )
+        .unwrap();
+        physics_world.world.add_constraint(Box::new(joint));
+
+        links.push(link);
+        previous = (link, Vector::new(20.0, 0.0));
+    }
+
+    let mut balls: Vec<BodyHandle> = Vec::new();
+
+    for _ in 0..10 {
+        balls.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-200.0..400.0),
+                    rng.random_range(500.0..1000.0),
+                ),
+                0.0,
+                0.001,
+                0.5,
+                Shape::new_circle(rng.random_range(20.0..40.0)),
+            )
+            .with_friction(0.6, 0.4),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        ));
+    }
+
+    let ground = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_rigid(
+            LinearState::new(Vector::new(0.0, -500.0), Vector::zeros(), f64::INFINITY),
+            0.3,
+            AngularState::new(0.0, 0.0, f64::INFINITY),
+            Shape::new_rectangle(Vector::new(1600.0, 50.0)),
+        )
+        .with_friction(0.6, 0.4)
+        .with_body_type(BodyType::Static),
+        Color::WHITE,
+    );
+
+    let bodies = [links, balls].concat();
+
+    physics_world
+        .world
+        .add_integrator(Box::new(SemiImplicitEuler::new(bodies.clone())));
+
+    physics_world
+        .world
+        .add_effector(Box::new(ConstantAcceleration::new(
+            bodies.clone(),
+            Vector::new(0.0, -200.0),
+        )));
+
+    physics_world
+        .world
+        .add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(
+            [bodies, vec![ground]].concat(),
+        )));
+}
@@ -2,10 +2,9 @@ use std::f64;
 
 use bevy::prelude::*;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
     collision::default::DefaultCollisionPipeline,
     effector::ConstantAcceleration,
-    id_map::Id,
     integrator::SemiImplicitEuler,
     types::math::Vector,
     world::World,
@@ -22,7 +21,7 @@ pub fn load(
 ) {
     let mut rng = rand::rng();
 
-    let mut bodies: Vec<Id> = Vec::new();
+    let mut bodies: Vec<BodyHandle> = Vec::new();
 
     for i in 0..1200 {
         bodies.push(spawn_physics_body(
@@ -65,7 +64,8 @@ pub fn load(
             1.0,
             AngularState::new(0.0, 0.0, 1000.0),
             Shape::new_circle(50.0),
-        ),
+        )
+        .with_body_type(BodyType::Kinematic),
         Color::WHITE,
     );
 
@@ -83,7 +83,8 @@ pub fn load(
             1.0,
             AngularState::new(0.0, 0.0, 1000.0),
             Shape::new_circle(50.0),
-        ),
+        )
+        .with_body_type(BodyType::Kinematic),
         Color::WHITE,
     );
 
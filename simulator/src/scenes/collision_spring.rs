@@ -2,10 +2,9 @@ use std::f64;
 
 use bevy::prelude::*;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
+    body::{AngularState, Body, BodyType, LinearState, Shape},
     collision::default::DefaultCollisionPipeline,
     effector::{ConstantAcceleration, Spring},
-    id_map::Id,
     integrator::SemiImplicitEuler,
     types::math::Vector,
     world::World,
@@ -32,7 +31,8 @@ pub fn load(
             1.0,
             AngularState::new(0.0, 0.0, 1000.0),
             Shape::new_circle(50.0),
-        ),
+        )
+        .with_body_type(BodyType::Static),
         Color::linear_rgb(
             rng.random_range(0.0..1.0),
             rng.random_range(0.0..1.0),
@@ -2,10 +2,9 @@ use std::f64;
 
 use bevy::prelude::*;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
     collision::default::DefaultCollisionPipeline,
     effector::ConstantAcceleration,
-    id_map::Id,
     integrator::SemiImplicitEuler,
     types::math::Vector,
     world::World,
@@ -22,7 +21,7 @@ pub fn load(
 ) {
     let mut rng = rand::rng();
 
-    let mut bodies: Vec<Id> = Vec::new();
+    let mut bodies: Vec<BodyHandle> = Vec::new();
 
     for i in 0..100 {
         bodies.push(spawn_physics_body(
@@ -30,19 +29,17 @@ pub fn load(
             meshes,
             materials,
             physics_world,
-            Body::new_rigid(
-                LinearState::new(
-                    Vector::new(
-                        rng.random_range(-800.0..800.0),
-                        rng.random_range(-400.0..1000.0),
-                    ),
-                    Vector::zeros(),
-                    1.0,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-800.0..800.0),
+                    rng.random_range(-400.0..1000.0),
                 ),
+                rng.random_range(0.0..f64::consts::TAU),
+                0.001,
                 1.0,
-                AngularState::new(rng.random_range(0.0..f64::consts::TAU), 0.0, 1000.0),
                 Shape::new_circle(rng.random_range(10.0..50.0)),
-            ),
+            )
+            .with_friction(0.6, 0.4),
             Color::linear_rgb(
                 rng.random_range(0.0..1.0),
                 rng.random_range(0.0..1.0),
@@ -61,7 +58,9 @@ pub fn load(
             0.3,
             AngularState::new(0.0, 0.0, f64::INFINITY),
             Shape::new_rectangle(Vector::new(1600.0, 50.0)),
-        ),
+        )
+        .with_friction(0.6, 0.4)
+        .with_body_type(BodyType::Static),
         Color::WHITE,
     );
 
@@ -0,0 +1,168 @@
+use std::f64;
+
+use bevy::prelude::*;
+use physics::{
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
+    collision::default::DefaultCollisionPipeline,
+    constraint::{prismatic::PrismaticJoint, revolute::RevoluteJoint},
+    effector::ConstantAcceleration,
+    integrator::SemiImplicitEuler,
+    types::math::Vector,
+};
+use rand::Rng;
+
+use crate::physics_helpers::{PhysicsWorld, spawn_physics_body};
+
+pub fn load(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    physics_world: &mut ResMut<PhysicsWorld>,
+) {
+    let mut rng = rand::rng();
+
+    let mut bodies: Vec<BodyHandle> = Vec::new();
+
+    let ground = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_rigid(
+            LinearState::new(Vector::new(0.0, -500.0), Vector::zeros(), f64::INFINITY),
+            0.3,
+            AngularState::new(0.0, 0.0, f64::INFINITY),
+            Shape::new_rectangle(Vector::new(1600.0, 50.0)),
+        )
+        .with_friction(0.6, 0.4)
+        .with_body_type(BodyType::Static),
+        Color::WHITE,
+    );
+
+    // Windmill turned by a motor
+    let blades = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_with_density(
+            Vector::new(-300.0, 0.0),
+            0.0,
+            0.001,
+            0.5,
+            Shape::new_rectangle(Vector::new(400.0, 20.0)),
+        )
+        .with_friction(0.6, 0.4),
+        Color::WHITE,
+    );
+    bodies.push(blades);
+
+    let windmill = RevoluteJoint::new_at_point(
+        [ground, blades],
+        Vector::new(-300.0, 0.0),
+        &physics_world.world,
+    )
+    .unwrap()
+    .with_motor(1.0, 1e8);
+    physics_world.world.add_constraint(Box::new(windmill));
+
+    // Door hanging from a hinge that only swings so far
+    let door = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_with_density(
+            Vector::new(400.0, 0.0),
+            0.0,
+            0.001,
+            0.5,
+            Shape::new_rectangle(Vector::new(300.0, 20.0)),
+        )
+        .with_friction(0.6, 0.4),
+        Color::WHITE,
+    );
+    bodies.push(door);
+
+    let hinge = RevoluteJoint::new_at_point(
+        [ground, door],
+        Vector::new(250.0, 0.0),
+        &physics_world.world,
+    )
+    .unwrap()
+    .with_limits(-1.0, 0.5);
+    physics_world.world.add_constraint(Box::new(hinge));
+
+    // Elevator that lifts whatever lands on it until it reaches the top
+    let platform = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_with_density(
+            Vector::new(0.0, -400.0),
+            0.0,
+            0.001,
+            0.5,
+            Shape::new_rectangle(Vector::new(200.0, 20.0)),
+        )
+        .with_friction(0.6, 0.4),
+        Color::WHITE,
+    );
+    bodies.push(platform);
+
+    let elevator = PrismaticJoint::new_at_point(
+        [ground, platform],
+        Vector::new(0.0, -400.0),
+        Vector::new(0.0, 1.0),
+        &physics_world.world,
+    )
+    .unwrap()
+    .with_limits(0.0, 300.0)
+    .with_motor(50.0, 1e8);
+    physics_world.world.add_constraint(Box::new(elevator));
+
+    for _ in 0..30 {
+        bodies.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-600.0..600.0),
+                    rng.random_range(300.0..1000.0),
+                ),
+                0.0,
+                0.001,
+                0.5,
+                Shape::new_circle(rng.random_range(15.0..30.0)),
+            )
+            .with_friction(0.6, 0.4),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        ));
+    }
+
+    physics_world
+        .world
+        .add_integrator(Box::new(SemiImplicitEuler::new(
+            [bodies.clone(), vec![ground]].concat(),
+        )));
+
+    physics_world
+        .world
+        .add_effector(Box::new(ConstantAcceleration::new(
+            bodies.clone(),
+            Vector::new(0.0, -200.0),
+        )));
+
+    physics_world
+        .world
+        .add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(
+            [bodies, vec![ground]].concat(),
+        )));
+}
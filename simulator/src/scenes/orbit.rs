@@ -5,7 +5,6 @@ use physics::{
     body::{AngularState, Body, LinearState, Shape},
     collision::default::DefaultCollisionPipeline,
     effector::{ConstantAcceleration, Gravity},
-    id_map::Id,
     integrator::SemiImplicitEuler,
     types::math::Vector,
     world::World,
@@ -2,10 +2,9 @@ use std::f64;
 
 use bevy::prelude::*;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
     collision::default::DefaultCollisionPipeline,
     effector::ConstantAcceleration,
-    id_map::Id,
     integrator::SemiImplicitEuler,
     types::math::Vector,
     world::World,
@@ -22,7 +21,7 @@ pub fn load(
 ) {
     let mut rng = rand::rng();
 
-    let mut bodies: Vec<Id> = Vec::new();
+    let mut bodies: Vec<BodyHandle> = Vec::new();
 
     for i in 0..30 {
         bodies.push(spawn_physics_body(
@@ -30,17 +29,14 @@ pub fn load(
             meshes,
             materials,
             physics_world,
-            Body::new_rigid(
-                LinearState::new(
-                    Vector::new(
-                        rng.random_range(-800.0..800.0),
-                        rng.random_range(-400.0..1000.0),
-                    ),
-                    Vector::zeros(),
-                    1.0,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-800.0..800.0),
+                    rng.random_range(-400.0..1000.0),
                 ),
+                rng.random_range(0.0..f64::consts::TAU),
+                0.001,
                 0.99,
-                AngularState::new(rng.random_range(0.0..f64::consts::TAU), 0.0, 1000.0),
                 Shape::new_polygon(vec![
                     Vector::new(40.0, 0.0),
                     Vector::new(30.0, 20.0),
@@ -53,7 +49,8 @@ pub fn load(
                     Vector::new(15.0, -20.0),
                     Vector::new(30.0, -10.0),
                 ]),
-            ),
+            )
+            .with_friction(0.6, 0.4),
             Color::linear_rgb(
                 rng.random_range(0.0..1.0),
                 rng.random_range(0.0..1.0),
@@ -68,17 +65,14 @@ pub fn load(
             meshes,
             materials,
             physics_world,
-            Body::new_rigid(
-                LinearState::new(
-                    Vector::new(
-                        rng.random_range(-800.0..800.0),
-                        rng.random_range(-400.0..1000.0),
-                    ),
-                    Vector::zeros(),
-                    1.0,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-800.0..800.0),
+                    rng.random_range(-400.0..1000.0),
                 ),
+                rng.random_range(0.0..f64::consts::TAU),
+                0.001,
                 0.99,
-                AngularState::new(rng.random_range(0.0..f64::consts::TAU), 0.0, 1000.0),
                 Shape::new_polygon(vec![
                     Vector::new(50.0, -10.0),
                     Vector::new(35.0, 25.0),
@@ -88,7 +82,8 @@ pub fn load(
                     Vector::new(-45.0, -10.0),
                     Vector::new(-20.0, -25.0),
                 ]),
-            ),
+            )
+            .with_friction(0.6, 0.4),
             Color::linear_rgb(
                 rng.random_range(0.0..1.0),
                 rng.random_range(0.0..1.0),
@@ -103,23 +98,21 @@ pub fn load(
             meshes,
             materials,
             physics_world,
-            Body::new_rigid(
-                LinearState::new(
-                    Vector::new(
-                        rng.random_range(-800.0..800.0),
-                        rng.random_range(-400.0..1000.0),
-                    ),
-                    Vector::zeros(),
-                    1.0,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(-800.0..800.0),
+                    rng.random_range(-400.0..1000.0),
                 ),
+                rng.random_range(0.0..f64::consts::TAU),
+                0.001,
                 0.99,
-                AngularState::new(rng.random_range(0.0..f64::consts::TAU), 0.0, 1000.0),
                 Shape::new_polygon(vec![
                     Vector::new(0.0, 15.0),
                     Vector::new(60.0, 0.0),
                     Vector::new(0.0, -15.0),
                 ]),
-            ),
+            )
+            .with_friction(0.6, 0.4),
             Color::linear_rgb(
                 rng.random_range(0.0..1.0),
                 rng.random_range(0.0..1.0),
@@ -138,7 +131,9 @@ pub fn load(
             0.3,
             AngularState::new(0.0, 0.0, f64::INFINITY),
             Shape::new_rectangle(Vector::new(1600.0, 50.0)),
-        ),
+        )
+        .with_friction(0.6, 0.4)
+        .with_body_type(BodyType::Static),
         Color::WHITE,
     );
 
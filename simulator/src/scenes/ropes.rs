@@ -0,0 +1,176 @@
+use std::f64;
+
+use bevy::prelude::*;
+use physics::{
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
+    collision::default::DefaultCollisionPipeline,
+    constraint::{pulley::PulleyJoint, rope::RopeJoint},
+    effector::ConstantAcceleration,
+    integrator::SemiImplicitEuler,
+    types::math::Vector,
+};
+use rand::Rng;
+
+use crate::physics_helpers::{PhysicsWorld, spawn_physics_body};
+
+pub fn load(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    physics_world: &mut ResMut<PhysicsWorld>,
+) {
+    let mut rng = rand::rng();
+
+    let mut bodies: Vec<BodyHandle> = Vec::new();
+
+    let ground = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_rigid(
+            LinearState::new(Vector::new(0.0, -500.0), Vector::zeros(), f64::INFINITY),
+            0.3,
+            AngularState::new(0.0, 0.0, f64::INFINITY),
+            Shape::new_rectangle(Vector::new(1600.0, 50.0)),
+        )
+        .with_friction(0.6, 0.4)
+        .with_body_type(BodyType::Static),
+        Color::WHITE,
+    );
+
+    // Wrecking ball swinging down into a wall
+    let hook = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_particle(
+            LinearState::new(Vector::new(-400.0, 300.0), Vector::zeros(), f64::INFINITY),
+            1.0,
+        )
+        .with_body_type(BodyType::Static),
+        Color::WHITE,
+    );
+
+    let wrecking_ball = spawn_physics_body(
+        commands,
+        meshes,
+        materials,
+        physics_world,
+        Body::new_with_density(
+            Vector::new(-800.0, 300.0),
+            0.0,
+            0.005,
+            0.2,
+            Shape::new_circle(40.0),
+        )
+        .with_friction(0.6, 0.4),
+        Color::WHITE,
+    );
+    bodies.push(wrecking_ball);
+
+    let rope = RopeJoint::new_auto_length(
+        [hook, wrecking_ball],
+        [Vector::zeros(), Vector::zeros()],
+        &physics_world.world,
+    )
+    .unwrap();
+    physics_world.world.add_constraint(Box::new(rope));
+
+    for i in 0..7 {
+        bodies.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(-400.0, -450.0 + 50.0 * i as f64),
+                0.0,
+                0.001,
+                0.2,
+                Shape::new_rectangle(Vector::new(50.0, 50.0)),
+            )
+            .with_friction(0.6, 0.4),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        ));
+    }
+
+    // Two platforms on a pulley, whatever lands on one lifts the other
+    let mut platforms: Vec<BodyHandle> = Vec::new();
+
+    for x in [200.0, 500.0] {
+        platforms.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(x, 0.0),
+                0.0,
+                0.001,
+                0.2,
+                Shape::new_rectangle(Vector::new(150.0, 20.0)),
+            )
+            .with_friction(0.6, 0.4),
+            Color::WHITE,
+        ));
+    }
+    bodies.extend(&platforms);
+
+    let pulley = PulleyJoint::new_auto_length(
+        [platforms[0], platforms[1]],
+        [Vector::new(200.0, 400.0), Vector::new(500.0, 400.0)],
+        [Vector::zeros(), Vector::zeros()],
+        1.0,
+        &physics_world.world,
+    )
+    .unwrap();
+    physics_world.world.add_constraint(Box::new(pulley));
+
+    for _ in 0..8 {
+        bodies.push(spawn_physics_body(
+            commands,
+            meshes,
+            materials,
+            physics_world,
+            Body::new_with_density(
+                Vector::new(
+                    rng.random_range(150.0..250.0),
+                    rng.random_range(200.0..1000.0),
+                ),
+                0.0,
+                0.001,
+                0.2,
+                Shape::new_circle(rng.random_range(15.0..25.0)),
+            )
+            .with_friction(0.6, 0.4),
+            Color::linear_rgb(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            ),
+        ));
+    }
+
+    physics_world
+        .world
+        .add_integrator(Box::new(SemiImplicitEuler::new(bodies.clone())));
+
+    physics_world
+        .world
+        .add_effector(Box::new(ConstantAcceleration::new(
+            bodies.clone(),
+            Vector::new(0.0, -200.0),
+        )));
+
+    physics_world
+        .world
+        .add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(
+            [bodies, vec![ground]].concat(),
+        )));
+}
@@ -2,10 +2,9 @@ use std::f64;
 
 use bevy::prelude::*;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
+    body::{AngularState, Body, BodyType, LinearState, Shape},
     collision::default::DefaultCollisionPipeline,
     effector::{ConstantAcceleration, Spring},
-    id_map::Id,
     integrator::SemiImplicitEuler,
     types::math::Vector,
     world::World,
@@ -30,7 +29,8 @@ pub fn load(
         Body::new_particle(
             LinearState::new(Vector::new(0.0, 200.0), Vector::zeros(), f64::INFINITY),
             1.0,
-        ),
+        )
+        .with_body_type(BodyType::Static),
         Color::linear_rgb(
             rng.random_range(0.0..1.0),
             rng.random_range(0.0..1.0),
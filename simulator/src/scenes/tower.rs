@@ -2,11 +2,11 @@ use std::f64;
 
 use bevy::prelude::*;
 use physics::{
-    body::{AngularState, Body, LinearState, Shape},
-    collision::default::DefaultCollisionPipeline,
+    body::{AngularState, Body, BodyHandle, BodyType, LinearState, Shape},
+    collision::{default::DefaultCollisionPipeline, sequential_impulse::SequentialImpulseResolver},
     effector::ConstantAcceleration,
-    id_map::Id,
     integrator::SemiImplicitEuler,
+    sleep::SleepSettings,
     types::math::Vector,
     world::World,
 };
@@ -22,7 +22,7 @@ pub fn load(
 ) {
     let mut rng = rand::rng();
 
-    let mut bodies: Vec<Id> = Vec::new();
+    let mut bodies: Vec<BodyHandle> = Vec::new();
 
     for i in 0..10 {
         bodies.push(spawn_physics_body(
@@ -30,16 +30,14 @@ pub fn load(
             meshes,
             materials,
             physics_world,
-            Body::new_rigid(
-                LinearState::new(
-                    Vector::new(0.0, 60.0 * (i as f64).powf(1.05)),
-                    Vector::zeros(),
-                    1.0,
-                ),
+            Body::new_with_density(
+                Vector::new(0.0, 60.0 * (i as f64).powf(1.05)),
+                0.0,
+                0.0002,
                 0.99,
-                AngularState::new(0.0, 0.0, 1000.0),
                 Shape::new_rectangle(Vector::new(100.0, 50.0)),
-            ),
+            )
+            .with_friction(0.6, 0.4),
             Color::linear_rgb(
                 rng.random_range(0.0..1.0),
                 rng.random_range(0.0..1.0),
@@ -72,7 +70,9 @@ pub fn load(
             0.3,
             AngularState::new(0.0, 0.0, f64::INFINITY),
             Shape::new_rectangle(Vector::new(1600.0, 50.0)),
-        ),
+        )
+        .with_friction(0.6, 0.4)
+        .with_body_type(BodyType::Static),
         Color::WHITE,
     );
 
@@ -91,7 +91,12 @@ pub fn load(
 
     physics_world
         .world
-        .add_collision_pipeline(Box::new(DefaultCollisionPipeline::new(
+        .add_collision_pipeline(Box::new(DefaultCollisionPipeline::with_resolver(
             [bodies.clone(), vec![circle, ground]].concat(),
+            SequentialImpulseResolver::new(10),
         )));
+
+    // Units are pixels with gravity at 200, so a settled box creeps well under 2 pixels a
+    // second and half a second of that is long enough to tell it won't topple
+    *physics_world.world.get_sleep_settings_mut() = SleepSettings::new(2.0, 0.05, 0.5);
 }
@@ -66,6 +66,22 @@ pub fn ui_pass(
             if ui.button("Orbit").clicked() {
                 load_event.write(LoadSceneEvent(PhysicsScene::Orbit));
             }
+
+            if ui.button("Chain").clicked() {
+                load_event.write(LoadSceneEvent(PhysicsScene::Chain));
+            }
+
+            if ui.button("Joints").clicked() {
+                load_event.write(LoadSceneEvent(PhysicsScene::Joints));
+            }
+
+            if ui.button("Bridge").clicked() {
+                load_event.write(LoadSceneEvent(PhysicsScene::Bridge));
+            }
+
+            if ui.button("Ropes").clicked() {
+                load_event.write(LoadSceneEvent(PhysicsScene::Ropes));
+            }
         });
 
     Ok(())